@@ -6,5 +6,19 @@ module Tracklib
 end
 
 module TracklibRwgps
+  class Error < StandardError; end
+  class SectionMissingError < Error; end
+  class SchemaMismatchError < Error; end
+  class KeyMaterialError < Error; end
+  class PolylineOptionError < Error; end
+  class SimplifyOptionError < Error; end
+  class BboxError < Error; end
+  class RegionError < Error; end
+  class SurfaceMappingError < Error; end
+  class PointSchemaError < Error; end
+  class ExportOptionError < Error; end
+  class StatsOptionError < Error; end
+  class DecodeError < Error; end
+
   Rutie.new(:tracklib_rwgps).init 'Init_Tracklib_Rwgps', __dir__
 end
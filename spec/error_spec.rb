@@ -0,0 +1,94 @@
+require "spec_helper"
+
+describe TracklibRwgps do
+  context "errors" do
+    let(:data) {
+      [{"x" => 40, "y" => 12, "e" => 1},
+       {"x" => 41, "y" => 800, "e" => 1}]
+    }
+    let(:schema) { Tracklib::Schema.new([["x", :f64, 6], ["y", :f64, 6], ["e", :f64, 1]]) }
+    let(:key) { "01234567890123456789012345678901" }
+    let(:polyline_opts) { TracklibRwgps::PolylineOptions::new([["y", 5], ["x", 5]]) }
+
+    it "raises SectionMissingError for a section that does not exist" do
+      section = Tracklib::Section::standard(schema, data)
+      reader = Tracklib::TrackReader::new(Tracklib::write_track([], [section]))
+
+      expect { TracklibRwgps::section_data_polyline(reader, 1, polyline_opts) }
+        .to raise_error(TracklibRwgps::SectionMissingError)
+    end
+
+    it "raises KeyMaterialError when an encrypted section is read without a key" do
+      section = Tracklib::Section::encrypted(schema, data, key)
+      reader = Tracklib::TrackReader::new(Tracklib::write_track([], [section]))
+
+      expect { TracklibRwgps::section_data_polyline(reader, 0, polyline_opts) }
+        .to raise_error(TracklibRwgps::KeyMaterialError)
+    end
+
+    it "raises KeyMaterialError when an encrypted section is read with the wrong key" do
+      section = Tracklib::Section::encrypted(schema, data, key)
+      reader = Tracklib::TrackReader::new(Tracklib::write_track([], [section]))
+
+      expect { TracklibRwgps::section_data_polyline(reader, 0, polyline_opts, key.reverse) }
+        .to raise_error(TracklibRwgps::KeyMaterialError)
+    end
+
+    it "raises TypeError for key material that isn't a String or nil" do
+      section = Tracklib::Section::encrypted(schema, data, key)
+      reader = Tracklib::TrackReader::new(Tracklib::write_track([], [section]))
+
+      expect { TracklibRwgps::section_data_polyline(reader, 0, polyline_opts, 42) }
+        .to raise_error(TypeError)
+      expect { TracklibRwgps::section_elevation_stats(reader, 0, 5, nil, [key]) }
+        .to raise_error(TypeError)
+    end
+
+    it "raises PolylineOptionError for invalid polyline options" do
+      expect { TracklibRwgps::PolylineOptions::new([["y", 5, 99], ["x", 5]]) }
+        .to raise_error(TracklibRwgps::PolylineOptionError)
+      expect { TracklibRwgps::PolylineOptions::new([["y", 5], ["x", 5], ["S", 5]]) }
+        .to raise_error(TracklibRwgps::PolylineOptionError)
+      expect { TracklibRwgps::PolylineOptions::new([["z", 5]]) }
+        .to raise_error(TracklibRwgps::PolylineOptionError)
+    end
+
+    it "raises PointSchemaError for invalid point schema columns" do
+      expect { TracklibRwgps::PointSchema::new([["S", "surface", 1]]) }
+        .to raise_error(TracklibRwgps::PointSchemaError)
+      expect { TracklibRwgps::PointSchema::new([["z", "altitude"]]) }
+        .to raise_error(TracklibRwgps::PointSchemaError)
+      expect { TracklibRwgps::PointSchema::new([["x", "lng"], ["x", "lon"]]) }
+        .to raise_error(TracklibRwgps::PointSchemaError)
+      expect { TracklibRwgps::PointSchema::new([["e", "x"]]) }
+        .to raise_error(TracklibRwgps::PointSchemaError)
+    end
+
+    it "raises BboxError for a malformed bbox" do
+      expect { TracklibRwgps::RoadClassMapping::new([-90, -180, 90]) }
+        .to raise_error(TracklibRwgps::BboxError)
+    end
+
+    it "raises BboxError for a bbox with inverted latitudes" do
+      expect { TracklibRwgps::RoadClassMapping::new([10, -20, -10, 20]) }
+        .to raise_error(TracklibRwgps::BboxError)
+    end
+
+    it "raises BboxError for a bbox that isn't finite" do
+      expect { TracklibRwgps::RoadClassMapping::new([-Float::INFINITY, -180, Float::INFINITY, 180]) }
+        .to raise_error(TracklibRwgps::BboxError)
+    end
+
+    it "raises RegionError for a malformed polygon" do
+      expect { TracklibRwgps::RoadClassMapping::from_wkt("POINT (0 0)") }
+        .to raise_error(TracklibRwgps::RegionError)
+      expect { TracklibRwgps::RoadClassMapping::from_geojson('{"type": "Polygon", "coordinates": [[[0, 0], [1, 1]]]}') }
+        .to raise_error(TracklibRwgps::RegionError)
+    end
+
+    it "raises errors that can be rescued as TracklibRwgps::Error" do
+      expect { TracklibRwgps::RoadClassMapping::new([]) }
+        .to raise_error(TracklibRwgps::Error)
+    end
+  end
+end
@@ -0,0 +1,61 @@
+require "spec_helper"
+require "json"
+
+describe TracklibRwgps do
+  context "geojson" do
+    let(:data) {
+      [{"x" => 0.0, "y" => 0.0, "e" => 1.0, "S" => 1},
+       {"x" => 0.001, "y" => 0.0, "e" => 2.0, "S" => 2},
+       {"x" => 0.001, "y" => 0.001, "e" => 3.0, "S" => 1},
+       {"x" => 0.002, "y" => 0.001, "e" => 4.0, "S" => 1}]
+    }
+    let(:reader) {
+      schema = Tracklib::Schema.new([["x", :f64, 6], ["y", :f64, 6], ["e", :f64, 1], ["S", :u64]])
+      section = Tracklib::Section::standard(schema, data)
+      Tracklib::TrackReader::new(Tracklib::write_track([], [section]))
+    }
+    let(:surface_mapping) {
+      sm = TracklibRwgps::SurfaceMapping::new(99)
+      sm.add_surface(1, "Paved")
+      sm.add_surface(2, "Gravel")
+      sm
+    }
+
+    it "can produce a LineString Feature" do
+      feature = JSON.parse(TracklibRwgps::section_data_geojson(reader, 0, surface_mapping, 0.0))
+
+      expect(feature["type"]).to eq("Feature")
+      expect(feature["geometry"]["type"]).to eq("LineString")
+      expect(feature["geometry"]["coordinates"]).to eq(data.map { |point| [point["x"], point["y"]] })
+      expect(feature["properties"]["elevation"]).to eq([1.0, 2.0, 3.0, 4.0])
+      expect(feature["properties"]["distance"].length).to eq(4)
+      expect(feature["properties"]["distance"][0]).to eq(0.0)
+    end
+
+    it "can produce one Feature per surface group" do
+      collection = JSON.parse(TracklibRwgps::section_data_geojson(reader, 0, surface_mapping, 0.0, true))
+
+      expect(collection["type"]).to eq("FeatureCollection")
+      expect(collection["features"].map { |feature| feature["properties"]["surface"] })
+        .to eq(["Paved", "Gravel"])
+      expect(collection["features"].map { |feature| feature["geometry"]["type"] })
+        .to eq(["MultiLineString", "MultiLineString"])
+      expect(collection["features"][0]["geometry"]["coordinates"])
+        .to eq([[[0.0, 0.0], [0.001, 0.0]],
+                [[0.001, 0.001], [0.002, 0.001]]])
+      expect(collection["features"][1]["properties"]["elevation"])
+        .to eq([[2.0, 3.0]])
+    end
+
+    it "smooths surface groups the way the points were simplified" do
+      simplify_opts = TracklibRwgps::SimplifyOptions::new(0.0)
+      simplify_opts.set_surface_smoothing("majority", 3.0)
+      collection = JSON.parse(TracklibRwgps::section_data_geojson(reader, 0, surface_mapping, simplify_opts, true))
+
+      expect(collection["features"].map { |feature| feature["properties"]["surface"] })
+        .to eq(["Paved"])
+      expect(collection["features"][0]["geometry"]["coordinates"])
+        .to eq([data.map { |point| [point["x"], point["y"]] }])
+    end
+  end
+end
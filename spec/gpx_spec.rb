@@ -0,0 +1,80 @@
+require "spec_helper"
+
+describe TracklibRwgps do
+  context "gpx" do
+    let(:data) {
+      [{"x" => -122.5, "y" => 45.25, "e" => 10.0, "t" => 1600000000},
+       {"x" => -122.25, "y" => 45.25, "e" => 11.0, "t" => 1600000010},
+       {"x" => -122.0, "y" => 45.5, "e" => 12.5}]
+    }
+    let(:reader) {
+      schema = Tracklib::Schema.new([["x", :f64, 6], ["y", :f64, 6], ["e", :f64, 1], ["t", :i64]])
+      section = Tracklib::Section::standard(schema, data)
+      Tracklib::TrackReader::new(Tracklib::write_track([], [section]))
+    }
+    let(:point_schema) { TracklibRwgps::PointSchema::new([["t", "t"]]) }
+    let(:header) {
+      '<?xml version="1.0" encoding="UTF-8"?>' \
+      '<gpx version="1.1" creator="tracklib_rwgps" xmlns="http://www.topografix.com/GPX/1/1">'
+    }
+
+    it "can write a track" do
+      expect(TracklibRwgps::section_data_gpx(reader, 0, "trk", "Morning Ride", nil, nil, nil, point_schema))
+        .to eq(header +
+               '<trk><name>Morning Ride</name><trkseg>' \
+               '<trkpt lat="45.25" lon="-122.5"><ele>10</ele><time>2020-09-13T12:26:40Z</time></trkpt>' \
+               '<trkpt lat="45.25" lon="-122.25"><ele>11</ele><time>2020-09-13T12:26:50Z</time></trkpt>' \
+               '<trkpt lat="45.5" lon="-122"><ele>12.5</ele></trkpt>' \
+               '</trkseg></trk></gpx>')
+    end
+
+    it "can write a simplified route" do
+      surface_mapping = TracklibRwgps::SurfaceMapping::new(99)
+
+      expect(TracklibRwgps::section_data_gpx(reader, 0, "rte", nil, surface_mapping, 1.0, nil, point_schema))
+        .to eq(header +
+               '<rte>' \
+               '<rtept lat="45.25" lon="-122.5"><ele>10</ele><time>2020-09-13T12:26:40Z</time></rtept>' \
+               '<rtept lat="45.5" lon="-122"><ele>12.5</ele></rtept>' \
+               '</rte></gpx>')
+    end
+
+    it "only reads times when the point schema names a time column" do
+      expect(TracklibRwgps::section_data_gpx(reader, 0, "trk", nil))
+        .to eq(header +
+               '<trk><trkseg>' \
+               '<trkpt lat="45.25" lon="-122.5"><ele>10</ele></trkpt>' \
+               '<trkpt lat="45.25" lon="-122.25"><ele>11</ele></trkpt>' \
+               '<trkpt lat="45.5" lon="-122"><ele>12.5</ele></trkpt>' \
+               '</trkseg></trk></gpx>')
+    end
+
+    it "can write a section without a time column" do
+      schema = Tracklib::Schema.new([["x", :f64, 6], ["y", :f64, 6], ["e", :f64, 1]])
+      section = Tracklib::Section::standard(schema, data.map { |point| point.reject { |key, _| key == "t" } })
+      untimed_reader = Tracklib::TrackReader::new(Tracklib::write_track([], [section]))
+
+      expect(TracklibRwgps::section_data_gpx(untimed_reader, 0, "trk", nil))
+        .to eq(header +
+               '<trk><trkseg>' \
+               '<trkpt lat="45.25" lon="-122.5"><ele>10</ele></trkpt>' \
+               '<trkpt lat="45.25" lon="-122.25"><ele>11</ele></trkpt>' \
+               '<trkpt lat="45.5" lon="-122"><ele>12.5</ele></trkpt>' \
+               '</trkseg></trk></gpx>')
+    end
+
+    it "needs both a surface mapping and a tolerance to simplify" do
+      surface_mapping = TracklibRwgps::SurfaceMapping::new(99)
+
+      expect { TracklibRwgps::section_data_gpx(reader, 0, "rte", nil, nil, 1.0) }
+        .to raise_error(TracklibRwgps::SimplifyOptionError)
+      expect { TracklibRwgps::section_data_gpx(reader, 0, "rte", nil, surface_mapping) }
+        .to raise_error(TracklibRwgps::SimplifyOptionError)
+    end
+
+    it "rejects unknown GPX types" do
+      expect { TracklibRwgps::section_data_gpx(reader, 0, "wpt") }
+        .to raise_error(TracklibRwgps::ExportOptionError)
+    end
+  end
+end
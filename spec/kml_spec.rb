@@ -0,0 +1,45 @@
+require "spec_helper"
+
+describe TracklibRwgps do
+  context "kml" do
+    let(:data) {
+      [{"x" => 0.0, "y" => 0.0, "e" => 1.0, "S" => 1},
+       {"x" => 0.001, "y" => 0.0, "e" => 2.0, "S" => 2},
+       {"x" => 0.001, "y" => 0.001, "e" => 3.0, "S" => 2}]
+    }
+    let(:reader) {
+      schema = Tracklib::Schema.new([["x", :f64, 6], ["y", :f64, 6], ["e", :f64, 1], ["S", :u64]])
+      section = Tracklib::Section::standard(schema, data)
+      Tracklib::TrackReader::new(Tracklib::write_track([], [section]))
+    }
+    let(:surface_mapping) {
+      sm = TracklibRwgps::SurfaceMapping::new(99)
+      sm.add_surface(1, "Paved")
+      sm.add_surface(2, "Gravel")
+      sm
+    }
+
+    it "can write a single line" do
+      kml = TracklibRwgps::section_data_kml(reader, 0, "Loop")
+
+      expect(kml).to include("<Document><name>Loop</name>")
+      expect(kml.scan(/<Placemark>/).length).to eq(1)
+      expect(kml).to include("<coordinates>0,0,1 0.001,0,2 0.001,0.001,3</coordinates>")
+    end
+
+    it "can style lines by surface group" do
+      kml = TracklibRwgps::section_data_kml(reader, 0, nil, surface_mapping, nil, true)
+
+      expect(kml.scan(/<Style id="([^"]*)">/).flatten).to eq(["surface-0", "surface-1"])
+      expect(kml.scan(/<name>([^<]*)<\/name><styleUrl>([^<]*)<\/styleUrl>/))
+        .to eq([["Paved", "#surface-0"], ["Gravel", "#surface-1"]])
+      expect(kml).to include("<coordinates>0,0,1 0.001,0,2</coordinates>")
+      expect(kml).to include("<coordinates>0.001,0,2 0.001,0.001,3</coordinates>")
+    end
+
+    it "needs a surface mapping to simplify" do
+      expect { TracklibRwgps::section_data_kml(reader, 0, nil, nil, 1.0) }
+        .to raise_error(TracklibRwgps::SimplifyOptionError)
+    end
+  end
+end
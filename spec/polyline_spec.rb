@@ -97,6 +97,25 @@ describe TracklibRwgps do
         .to eq([12.0, 40.0, 800.0, 41.0])
     end
 
+    it "can read columns with other names through a PointSchema" do
+      data = [{"lng" => 40, "lat" => 12, "ele" => 2, "surface" => 10},
+              {"lng" => 41, "lat" => 800, "ele" => 2, "surface" => 20}]
+      schema = Tracklib::Schema.new([["lng", :f64, 7], ["lat", :f64, 7], ["ele", :f64, 2], ["surface", :u64]])
+      section = Tracklib::Section::standard(schema, data)
+      buf = Tracklib::write_track([], [section])
+      reader = Tracklib::TrackReader::new(buf)
+
+      point_schema = TracklibRwgps::PointSchema::new([["x", "lng", 7],
+                                                      ["y", "lat", 7],
+                                                      ["e", "ele", 2],
+                                                      ["S", "surface"]])
+      polyline_opts = TracklibRwgps::PolylineOptions::new([["y", 5], ["x", 5], ["S", 0, 99]])
+
+      polyline = TracklibRwgps::section_data_polyline(reader, 0, polyline_opts, nil, point_schema)
+      expect(decode_polyline(polyline, [5, 5, 0]))
+        .to eq([12.0, 40.0, 10.0, 800.0, 41.0, 20.0])
+    end
+
     it "can compute the 'd' field" do
       data = [{"x" => -122.402, "y" => 72.1, "e" => 2},
               {"x" => -122.500, "y" => 72.309, "e" => 2}]
@@ -109,5 +128,28 @@ describe TracklibRwgps do
       expect(decode_polyline(polyline, [5, 5, 5]))
         .to eq([72.1, -122.402, 0.0, 72.309, -122.5, 23477.14945])
     end
+
+    it "can decode polylines" do
+      data = [{"x" => 40, "y" => 12, "e" => 1, "S" => 10},
+              {"x" => 100, "y" => 0.5, "e" => 1},
+              {"x" => 41, "y" => 800, "e" => 1, "S" => 20}]
+      schema = Tracklib::Schema.new([["x", :f64, 6], ["y", :f64, 6], ["e", :f64, 1], ["S", :u64]])
+      section = Tracklib::Section::standard(schema, data)
+      buf = Tracklib::write_track([], [section])
+      reader = Tracklib::TrackReader::new(buf)
+
+      polyline_opts = TracklibRwgps::PolylineOptions::new([["y", 5], ["x", 5], ["e", 1], ["S", 0, 99]])
+      polyline = TracklibRwgps::section_data_polyline(reader, 0, polyline_opts)
+      expect(TracklibRwgps::decode_polyline(polyline, polyline_opts))
+        .to eq([{"y" => 12.0, "x" => 40.0, "e" => 1.0, "S" => 10},
+                {"y" => 0.5, "x" => 100.0, "e" => 1.0},
+                {"y" => 800.0, "x" => 41.0, "e" => 1.0, "S" => 20}])
+    end
+
+    it "raises DecodeError for malformed polylines" do
+      polyline_opts = TracklibRwgps::PolylineOptions::new([["y", 5], ["x", 5]])
+      expect { TracklibRwgps::decode_polyline("_p~iF", polyline_opts) }
+        .to raise_error(TracklibRwgps::DecodeError)
+    end
   end
 end
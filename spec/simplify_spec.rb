@@ -253,6 +253,266 @@ describe TracklibRwgps do
         .to eq(expected.map { |row| row["x"] })
     end
 
+    it "accepts SimplifyOptions in place of a tolerance" do
+      reader = Tracklib::TrackReader::new(buf)
+
+      expected = [data[0],
+                  data[5],
+                  data[7],
+                  data[12],
+                  data[17],
+                  data[21],
+                  data[24],
+                  data[26],
+                  data[31],
+                  data[33],
+                  data[36],
+                  data[41],
+                  data[43],
+                  data[-1]]
+
+      simplify_opts = TracklibRwgps::SimplifyOptions::new(0.0001)
+      simplify_opts.set_algorithm("rdp")
+
+      expect(TracklibRwgps::section_data_simplified(reader,
+                                                    0,
+                                                    surface_mapping,
+                                                    simplify_opts))
+        .to eq(expected)
+    end
+
+    it "works with Visvalingam simplification" do
+      reader = Tracklib::TrackReader::new(buf)
+
+      expected = [data[0],
+                  data[11],
+                  data[30],
+                  data[-1]]
+
+      simplify_opts = TracklibRwgps::SimplifyOptions::new(0.001)
+      simplify_opts.set_algorithm("visvalingam")
+
+      expect(TracklibRwgps::section_data_simplified(reader,
+                                                    0,
+                                                    surface_mapping,
+                                                    simplify_opts))
+        .to eq(expected)
+      expect(TracklibRwgps::section_column_simplified(reader,
+                                                      0,
+                                                      "x",
+                                                      surface_mapping,
+                                                      simplify_opts))
+        .to eq(expected.map { |row| row["x"] })
+    end
+
+    it "can simplify to a maximum number of points" do
+      reader = Tracklib::TrackReader::new(buf)
+
+      expected = [data[0],
+                  data[12],
+                  data[31],
+                  data[36],
+                  data[43],
+                  data[-1]]
+
+      simplify_opts = TracklibRwgps::SimplifyOptions::new(0.0)
+      simplify_opts.set_max_points(6)
+
+      expect(TracklibRwgps::section_data_simplified(reader,
+                                                    0,
+                                                    surface_mapping,
+                                                    simplify_opts))
+        .to eq(expected)
+      expect(TracklibRwgps::section_column_simplified(reader,
+                                                      0,
+                                                      "x",
+                                                      surface_mapping,
+                                                      simplify_opts))
+        .to eq(expected.map { |row| row["x"] })
+    end
+
+    it "can simplify with a tolerance in meters" do
+      reader = Tracklib::TrackReader::new(buf)
+
+      expected = [data[0],
+                  data[5],
+                  data[7],
+                  data[12],
+                  data[17],
+                  data[21],
+                  data[24],
+                  data[26],
+                  data[31],
+                  data[33],
+                  data[36],
+                  data[43],
+                  data[-1]]
+
+      simplify_opts = TracklibRwgps::SimplifyOptions::new(10.0)
+      simplify_opts.set_units("meters")
+
+      expect(TracklibRwgps::section_data_simplified(reader,
+                                                    0,
+                                                    surface_mapping,
+                                                    simplify_opts))
+        .to eq(expected)
+    end
+
+    it "can simplify in 3D with elevation" do
+      reader = Tracklib::TrackReader::new(buf)
+
+      expected = [data[0],
+                  data[5],
+                  data[7],
+                  data[12],
+                  data[17],
+                  data[21],
+                  data[24],
+                  data[26],
+                  data[31],
+                  data[33],
+                  data[36],
+                  data[43],
+                  data[-1]]
+
+      simplify_opts = TracklibRwgps::SimplifyOptions::new(10.0)
+      simplify_opts.set_elevation("combined")
+
+      expect(TracklibRwgps::section_data_simplified(reader,
+                                                    0,
+                                                    surface_mapping,
+                                                    simplify_opts))
+        .to eq(expected)
+    end
+
+    it "returns time and the speed derived from it" do
+      timed_data = [{"x" => 0.0, "y" => 0.0, "e" => 1.0, "t" => 1600000000},
+                    {"x" => 0.0001, "y" => 0.001, "e" => 1.0, "t" => 1600000010},
+                    {"x" => 0.0, "y" => 0.002, "e" => 1.0}]
+      schema = Tracklib::Schema.new([["x", :f64, 6], ["y", :f64, 6], ["e", :f64, 1], ["t", :i64]])
+      section = Tracklib::Section::standard(schema, timed_data)
+      reader = Tracklib::TrackReader::new(Tracklib::write_track([], [section]))
+
+      point_schema = TracklibRwgps::PointSchema::new([["t", "t"]])
+      simplified = TracklibRwgps::section_data_simplified(reader, 0, surface_mapping, 0.0, nil, point_schema)
+      expect(simplified.map { |row| row["t"] }).to eq([1600000000, 1600000010, nil])
+      expect(simplified[0]).not_to have_key("speed")
+      expect(simplified[1]["speed"]).to be_within(0.001).of(11.175)
+      expect(simplified[2]).not_to have_key("speed")
+
+      polyline_opts = TracklibRwgps::PolylineOptions::new([["y", 5], ["x", 5], ["t", 0]])
+      polyline = TracklibRwgps::section_data_simplified_polyline(reader,
+                                                                0,
+                                                                surface_mapping,
+                                                                0.0,
+                                                                polyline_opts,
+                                                                nil,
+                                                                point_schema)
+      expect(TracklibRwgps::decode_polyline(polyline, polyline_opts).map { |point| point["t"] })
+        .to eq([1600000000, 1600000010, nil])
+    end
+
+    it "can smooth surface groups before simplifying" do
+      surfaces = [1, 1, 20, 1, 1, 1, 20, 1, 20, 20, 20, 20]
+      surface_data = surfaces.each_with_index.map { |s, i| {"x" => i * 0.001, "y" => 0.0, "e" => 0.0, "S" => s} }
+      schema = Tracklib::Schema.new([["x", :f64, 6], ["y", :f64, 6], ["e", :f64, 1], ["S", :u64]])
+      section = Tracklib::Section::standard(schema, surface_data)
+      reader = Tracklib::TrackReader::new(Tracklib::write_track([], [section]))
+
+      mapping = TracklibRwgps::SurfaceMapping::new(99)
+      mapping.add_surface(1, "Paved")
+      mapping.add_surface(20, "Gravel")
+
+      simplify_opts = TracklibRwgps::SimplifyOptions::new(0.0001)
+      expect(TracklibRwgps::section_column_simplified(reader, 0, "S", mapping, simplify_opts))
+        .to eq([1, 1, 20, 1, 1, 20, 1, 20, 20])
+
+      simplify_opts.set_surface_smoothing("points", 2.0)
+      expect(TracklibRwgps::section_column_simplified(reader, 0, "S", mapping, simplify_opts))
+        .to eq([1, 1, 20, 20])
+
+      simplify_opts.set_surface_smoothing("none")
+      expect(TracklibRwgps::section_column_simplified(reader, 0, "S", mapping, simplify_opts))
+        .to eq([1, 1, 20, 1, 1, 20, 1, 20, 20])
+    end
+
+    it "can keep anchors where the road class changes" do
+      road_classes = [1, 1, 1, 2, 2, 2, 1]
+      road_data = road_classes.each_with_index.map { |r, i| {"x" => i * 0.001, "y" => 0.0, "e" => 0.0, "R" => r} }
+      schema = Tracklib::Schema.new([["x", :f64, 6], ["y", :f64, 6], ["e", :f64, 1], ["R", :u64]])
+      section = Tracklib::Section::standard(schema, road_data)
+      reader = Tracklib::TrackReader::new(Tracklib::write_track([], [section]))
+
+      simplify_opts = TracklibRwgps::SimplifyOptions::new(0.0001)
+      expect(TracklibRwgps::section_column_simplified(reader, 0, "x", surface_mapping, simplify_opts))
+        .to eq([0.0, 0.006])
+
+      simplify_opts.set_keep_road_class_changes(true)
+      expect(TracklibRwgps::section_column_simplified(reader, 0, "x", surface_mapping, simplify_opts))
+        .to eq([0.0, 0.002, 0.003, 0.005, 0.006])
+    end
+
+    it "can simplify at several tolerances at once" do
+      reader = Tracklib::TrackReader::new(buf)
+      tolerances = [0.1, 0.00001, 0.0001]
+      polyline_opts = TracklibRwgps::PolylineOptions::new([["y", 5], ["x", 5]])
+
+      expect(TracklibRwgps::section_data_simplified_polyline_levels(reader, 0, surface_mapping, tolerances, polyline_opts))
+        .to eq(tolerances.map { |tolerance|
+                 TracklibRwgps::section_data_simplified_polyline(reader, 0, surface_mapping, tolerance, polyline_opts)
+               })
+
+      # Indexes count the empty points too
+      expect(TracklibRwgps::section_simplified_indexes_levels(reader, 0, surface_mapping, tolerances))
+        .to eq([[0, 48],
+                [0, 1, 5, 6, 7, 8, 10, 11, 12] + (14..48).to_a,
+                [0, 5, 7, 12, 17, 21, 24, 26, 31, 33, 36, 41, 43, 48]])
+
+      simplify_opts = TracklibRwgps::SimplifyOptions::new(0.0)
+      simplify_opts.set_algorithm("visvalingam")
+      expect(TracklibRwgps::section_simplified_indexes_levels(reader, 0, surface_mapping, [0.001], simplify_opts))
+        .to eq([[0, 11, 30, 48]])
+    end
+
+    it "returns the tolerance at which each point is removed" do
+      reader = Tracklib::TrackReader::new(buf)
+
+      significance = TracklibRwgps::section_simplified_significance(reader, 0, surface_mapping)
+      expect(significance.length).to eq(data.length)
+      expect(significance.values_at(0, 2, 3, 4, -1)).to eq([Float::INFINITY, nil, nil, nil, Float::INFINITY])
+
+      [0.00001, 0.0001, 0.1].each do |tolerance|
+        kept = significance.each_index.select { |i| significance[i] && significance[i] > tolerance }
+        expect([kept]).to eq(TracklibRwgps::section_simplified_indexes_levels(reader, 0, surface_mapping, [tolerance]))
+      end
+    end
+
+    it "accepts an integer surface smoothing value" do
+      simplify_opts = TracklibRwgps::SimplifyOptions::new(0.001)
+      simplify_opts.set_surface_smoothing("points", 3)
+      expect(simplify_opts.to_s).to include("MinPoints(3)")
+      expect { simplify_opts.set_surface_smoothing("points", "3") }
+        .to raise_error(TypeError)
+    end
+
+    it "rejects unknown simplification settings" do
+      simplify_opts = TracklibRwgps::SimplifyOptions::new(0.001)
+      expect { simplify_opts.set_algorithm("douglas") }
+        .to raise_error(TracklibRwgps::SimplifyOptionError)
+      expect { simplify_opts.set_units("furlongs") }
+        .to raise_error(TracklibRwgps::SimplifyOptionError)
+      expect { simplify_opts.set_elevation("altitude") }
+        .to raise_error(TracklibRwgps::SimplifyOptionError)
+      expect { simplify_opts.set_surface_smoothing("median", 3.0) }
+        .to raise_error(TracklibRwgps::SimplifyOptionError)
+      expect { simplify_opts.set_surface_smoothing("majority", 4.0) }
+        .to raise_error(TracklibRwgps::SimplifyOptionError)
+      expect { simplify_opts.set_max_points(0) }
+        .to raise_error(TracklibRwgps::SimplifyOptionError)
+      expect { simplify_opts.set_max_points(-1) }
+        .to raise_error(TracklibRwgps::SimplifyOptionError)
+    end
+
     it "can work on encrypted sections" do
       schema = Tracklib::Schema.new([["x", :f64, 6], ["y", :f64, 6], ["e", :f64, 1], ["d", :f64, 1]])
       key = "01234567890123456789012345678901"
@@ -0,0 +1,146 @@
+require "spec_helper"
+
+describe TracklibRwgps do
+  context "elevation stats" do
+    let(:schema) { Tracklib::Schema.new([["x", :f64, 6], ["y", :f64, 6], ["e", :f64, 1]]) }
+
+    # Points 0.001 degrees of latitude (~111m) apart
+    let(:reader) {
+      data = [100.0, 101.0, 100.0, 101.0, 110.0, 108.0, 120.0, 90.0].each_with_index.map do |e, i|
+        {"x" => 0.0, "y" => i * 0.001, "e" => e}
+      end
+      section = Tracklib::Section::standard(schema, data)
+      Tracklib::TrackReader::new(Tracklib::write_track([], [section]))
+    }
+
+    it "can compute elevation stats" do
+      stats = TracklibRwgps::section_elevation_stats(reader, 0, 0.0)
+
+      expect(stats["ascent"]).to be_within(0.001).of(23.0)
+      expect(stats["descent"]).to be_within(0.001).of(33.0)
+      expect(stats["min_elevation"]).to eq(90.0)
+      expect(stats["max_elevation"]).to eq(120.0)
+      expect(stats["max_grade"]).to be_within(0.01).of(12.0 / 111.195 * 100.0)
+    end
+
+    it "ignores changes smaller than the hysteresis" do
+      stats = TracklibRwgps::section_elevation_stats(reader, 0, 5.0)
+
+      expect(stats["ascent"]).to be_within(0.001).of(20.0)
+      expect(stats["descent"]).to be_within(0.001).of(30.0)
+    end
+
+    it "has no max grade for tracks shorter than the grade distance" do
+      stats = TracklibRwgps::section_elevation_stats(reader, 0, 0.0, 10000.0)
+
+      expect(stats["max_grade"]).to be_nil
+    end
+
+    it "accepts integer hysteresis and grade distance" do
+      stats = TracklibRwgps::section_elevation_stats(reader, 0, 5, 10000)
+
+      expect(stats["ascent"]).to be_within(0.001).of(20.0)
+      expect(stats["max_grade"]).to be_nil
+    end
+
+    it "rejects a hysteresis or grade distance that isn't a number" do
+      expect { TracklibRwgps::section_elevation_stats(reader, 0, "5") }
+        .to raise_error(TypeError)
+      expect { TracklibRwgps::section_elevation_stats(reader, 0, 5.0, "100") }
+        .to raise_error(TypeError)
+    end
+
+    it "rejects a hysteresis or grade distance out of range" do
+      expect { TracklibRwgps::section_elevation_stats(reader, 0, -1.0) }
+        .to raise_error(TracklibRwgps::StatsOptionError)
+      expect { TracklibRwgps::section_elevation_stats(reader, 0, Float::NAN) }
+        .to raise_error(TracklibRwgps::StatsOptionError)
+      expect { TracklibRwgps::section_elevation_stats(reader, 0, 5.0, 0) }
+        .to raise_error(TracklibRwgps::StatsOptionError)
+      expect { TracklibRwgps::section_elevation_stats(reader, 0, 5.0, Float::INFINITY) }
+        .to raise_error(TracklibRwgps::StatsOptionError)
+    end
+
+    it "returns nil for a section without points" do
+      section = Tracklib::Section::standard(schema, [])
+      empty_reader = Tracklib::TrackReader::new(Tracklib::write_track([], [section]))
+
+      expect(TracklibRwgps::section_elevation_stats(empty_reader, 0, 0.0)).to be_nil
+    end
+  end
+
+  context "surface groups" do
+    # Points 0.001 degrees of latitude (~111m) apart
+    let(:reader) {
+      schema = Tracklib::Schema.new([["x", :f64, 6], ["y", :f64, 6], ["e", :f64, 1], ["S", :u64]])
+      data = [1, 2, 20, 1, 5].each_with_index.map do |s, i|
+        {"x" => 0.0, "y" => i * 0.001, "e" => 0.0, "S" => s}
+      end
+      section = Tracklib::Section::standard(schema, data)
+      Tracklib::TrackReader::new(Tracklib::write_track([], [section]))
+    }
+    let(:surface_mapping) {
+      sm = TracklibRwgps::SurfaceMapping::new(99)
+      sm.add_surface(1, "Paved")
+      sm.add_surface(2, "Paved")
+      sm.add_surface(20, "Gravel")
+      sm
+    }
+
+    it "can sum meters per surface group" do
+      summary = TracklibRwgps::section_surface_summary(reader, 0, surface_mapping)
+
+      expect(summary["groups"].keys).to contain_exactly("Paved", "Gravel")
+      expect(summary["groups"]["Paved"]).to be_within(0.01).of(111.195 * 3)
+      expect(summary["groups"]["Gravel"]).to be_within(0.01).of(111.195)
+      expect(summary["unknown"]).to eq(0.0)
+    end
+
+    it "counts points without a surface group as unknown" do
+      summary = TracklibRwgps::section_surface_summary(reader, 0, TracklibRwgps::SurfaceMapping::new(99))
+
+      expect(summary["groups"]).to eq({})
+      expect(summary["unknown"]).to be_within(0.01).of(111.195 * 4)
+    end
+
+    it "keeps a surface group named unknown apart from the unknown meters" do
+      mapping = TracklibRwgps::SurfaceMapping::new(99)
+      mapping.add_surface(20, "unknown")
+
+      summary = TracklibRwgps::section_surface_summary(reader, 0, mapping)
+
+      expect(summary["groups"]["unknown"]).to be_within(0.01).of(111.195)
+      expect(summary["unknown"]).to be_within(0.01).of(111.195 * 3)
+    end
+
+    it "can split a section into surface segments" do
+      segments = TracklibRwgps::section_surface_segments(reader, 0, surface_mapping)
+
+      expect(segments.map { |segment| [segment["start_index"], segment["end_index"], segment["group"]] })
+        .to eq([[0, 2, "Paved"], [2, 3, "Gravel"], [3, 4, "Paved"]])
+      expect(segments[0]["start_d"]).to eq(0.0)
+      expect(segments[1]["start_d"]).to be_within(0.01).of(111.195 * 2)
+      expect(segments[1]["end_d"]).to be_within(0.01).of(111.195 * 3)
+    end
+
+    it "can merge short surface segments into their neighbours" do
+      segments = TracklibRwgps::section_surface_segments(reader, 0, surface_mapping, 150.0)
+
+      expect(segments.map { |segment| [segment["start_index"], segment["end_index"], segment["group"]] })
+        .to eq([[0, 4, "Paved"]])
+      expect(segments[0]["end_d"]).to be_within(0.01).of(111.195 * 4)
+    end
+
+    it "accepts an integer min length" do
+      segments = TracklibRwgps::section_surface_segments(reader, 0, surface_mapping, 150)
+
+      expect(segments.map { |segment| [segment["start_index"], segment["end_index"], segment["group"]] })
+        .to eq([[0, 4, "Paved"]])
+    end
+
+    it "rejects a min length that isn't a number" do
+      expect { TracklibRwgps::section_surface_segments(reader, 0, surface_mapping, "150") }
+        .to raise_error(TypeError)
+    end
+  end
+end
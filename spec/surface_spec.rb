@@ -16,5 +16,93 @@ describe TracklibRwgps do
 
       sm.add_road_class_mapping(rcm)
     end
+
+    it "can use a bbox crossing the antimeridian" do
+      schema = Tracklib::Schema.new([["x", :f64, 6], ["y", :f64, 6], ["e", :f64, 1], ["S", :u64], ["R", :u64]])
+      data = [178.0, 179.0, -179.0, -170.0, -169.0].map do |x|
+        {"x" => x, "y" => -17.0, "e" => 0.0, "S" => 99, "R" => 10}
+      end
+      section = Tracklib::Section::standard(schema, data)
+      reader = Tracklib::TrackReader::new(Tracklib::write_track([], [section]))
+
+      rcm = TracklibRwgps::RoadClassMapping::new([-20, 175, -10, -175])
+      rcm.add_road_class(10, 0)
+      sm = TracklibRwgps::SurfaceMapping::new(99)
+      sm.add_surface(0, "Paved")
+      sm.add_road_class_mapping(rcm)
+
+      segments = TracklibRwgps::section_surface_segments(reader, 0, sm)
+      expect(segments.map { |segment| [segment["start_index"], segment["end_index"], segment["group"]] })
+        .to eq([[0, 3, "Paved"], [3, 4, nil]])
+    end
+  end
+
+  context "Road class regions" do
+    # An L shaped region, its bbox covers the empty top right quarter
+    let(:wkt) { "POLYGON ((0 0, 10 0, 10 5, 5 5, 5 10, 0 10, 0 0))" }
+    let(:geojson) {
+      '{"type": "Feature", "properties": {}, "geometry": {"type": "Polygon", ' \
+        '"coordinates": [[[0, 0], [10, 0], [10, 5], [5, 5], [5, 10], [0, 10], [0, 0]]]}}'
+    }
+    let(:reader) {
+      schema = Tracklib::Schema.new([["x", :f64, 6], ["y", :f64, 6], ["e", :f64, 1], ["S", :u64], ["R", :u64]])
+      data = [[1, 1], [2, 8], [8, 8], [9, 1], [9, 2]].map do |x, y|
+        {"x" => x.to_f, "y" => y.to_f, "e" => 0.0, "S" => 99, "R" => 10}
+      end
+      section = Tracklib::Section::standard(schema, data)
+      Tracklib::TrackReader::new(Tracklib::write_track([], [section]))
+    }
+
+    def groups(rcm)
+      rcm.add_road_class(10, 0)
+      sm = TracklibRwgps::SurfaceMapping::new(99)
+      sm.add_surface(0, "Paved")
+      sm.add_road_class_mapping(rcm)
+
+      TracklibRwgps::section_surface_segments(reader, 0, sm).map do |segment|
+        [segment["start_index"], segment["end_index"], segment["group"]]
+      end
+    end
+
+    it "can use a WKT polygon as the region" do
+      expect(groups(TracklibRwgps::RoadClassMapping::from_wkt(wkt)))
+        .to eq([[0, 2, "Paved"], [2, 3, nil], [3, 4, "Paved"]])
+    end
+
+    it "can use a GeoJSON polygon as the region" do
+      expect(groups(TracklibRwgps::RoadClassMapping::from_geojson(geojson)))
+        .to eq([[0, 2, "Paved"], [2, 3, nil], [3, 4, "Paved"]])
+    end
+  end
+
+  context "Surface mapping config" do
+    let(:json) {
+      '{"unknown_surface_id":99,"groups":{"0":"Paved","20":"Gravel"},"road_class_mappings":[' \
+        '{"bbox":[-90.0,-180.0,90.0,180.0],"road_classes":{"10":20}},' \
+        '{"polygons":[[[[0.0,0.0],[10.0,0.0],[10.0,5.0],[0.0,5.0]]]],"road_classes":{"10":0}}]}'
+    }
+
+    it "can be written as JSON" do
+      sm = TracklibRwgps::SurfaceMapping::new(99)
+      sm.add_surface(20, "Gravel")
+      sm.add_surface(0, "Paved")
+      rcm = TracklibRwgps::RoadClassMapping::new([-90, -180, 90, 180])
+      rcm.add_road_class(10, 20)
+      sm.add_road_class_mapping(rcm)
+      rcm = TracklibRwgps::RoadClassMapping::from_wkt("POLYGON ((0 0, 10 0, 10 5, 0 5))")
+      rcm.add_road_class(10, 0)
+      sm.add_road_class_mapping(rcm)
+
+      expect(sm.to_json).to eq(json)
+    end
+
+    it "can be read from JSON" do
+      expect(TracklibRwgps::SurfaceMapping::from_json(json).to_json).to eq(json)
+    end
+
+    it "raises SurfaceMappingError for an invalid config" do
+      expect { TracklibRwgps::SurfaceMapping::from_json('{"groups":{}}') }
+        .to raise_error(TracklibRwgps::SurfaceMappingError)
+    end
   end
 end
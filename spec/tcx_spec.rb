@@ -0,0 +1,48 @@
+require "spec_helper"
+
+describe TracklibRwgps do
+  context "tcx" do
+    let(:data) {
+      [{"x" => -122.402, "y" => 72.1, "e" => 2.0, "t" => 1600000000},
+       {"x" => -122.5, "y" => 72.309, "e" => 3.0, "t" => 1600003600}]
+    }
+    let(:reader) {
+      schema = Tracklib::Schema.new([["x", :f64, 6], ["y", :f64, 6], ["e", :f64, 1], ["t", :i64]])
+      section = Tracklib::Section::standard(schema, data)
+      Tracklib::TrackReader::new(Tracklib::write_track([], [section]))
+    }
+
+    it "can write a course with cumulative distance" do
+      point_schema = TracklibRwgps::PointSchema::new([["t", "t"]])
+      tcx = TracklibRwgps::section_data_tcx(reader, 0, "Loop", nil, nil, nil, point_schema)
+
+      expect(tcx).to start_with('<?xml version="1.0" encoding="UTF-8"?>' \
+                                '<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">' \
+                                '<Courses><Course><Name>Loop</Name>' \
+                                '<Lap><TotalTimeSeconds>3600</TotalTimeSeconds>')
+      expect(tcx.scan(/<Trackpoint>/).length).to eq(2)
+      lap_distance, *point_distances = tcx.scan(/<DistanceMeters>([^<]*)<\/DistanceMeters>/).flatten.map(&:to_f)
+      expect(lap_distance).to be_within(0.001).of(23477.14945)
+      expect(point_distances[0]).to eq(0.0)
+      expect(point_distances[1]).to be_within(0.001).of(23477.14945)
+      expect(tcx).to include("<Time>2020-09-13T13:26:40Z</Time>")
+    end
+
+    it "names and times every point of an untimed, unnamed course" do
+      tcx = TracklibRwgps::section_data_tcx(reader, 0)
+
+      expect(tcx).to include("<Course><Name>Course</Name>")
+      expect(tcx.scan(/<Trackpoint><Time>[^<]*<\/Time>/))
+        .to eq(["<Trackpoint><Time>1970-01-01T00:00:00Z</Time>", "<Trackpoint><Time>1970-01-01T01:18:15Z</Time>"])
+      expect(TracklibRwgps::section_data_tcx(reader, 0, "Around the Lake & Back"))
+        .to include("<Name>Around the Lake</Name>")
+    end
+
+    it "needs both a surface mapping and a tolerance to simplify" do
+      expect { TracklibRwgps::section_data_tcx(reader, 0, "Loop", nil, 1.0) }
+        .to raise_error(TracklibRwgps::SimplifyOptionError)
+      expect { TracklibRwgps::section_data_tcx(reader, 0, "Loop", TracklibRwgps::SurfaceMapping::new(99)) }
+        .to raise_error(TracklibRwgps::SimplifyOptionError)
+    end
+  end
+end
@@ -0,0 +1,73 @@
+require "spec_helper"
+
+describe TracklibRwgps do
+  context "tracks" do
+    let(:schema) { Tracklib::Schema.new([["x", :f64, 6], ["y", :f64, 6], ["e", :f64, 1]]) }
+    let(:first_data) {
+      [{"x" => -122.402, "y" => 72.1, "e" => 2},
+       {"x" => -122.500, "y" => 72.309, "e" => 2}]
+    }
+    let(:second_data) {
+      [{"x" => -122.402, "y" => 72.1, "e" => 3}]
+    }
+    let(:key) { "01234567890123456789012345678901" }
+    let (:surface_mapping) { TracklibRwgps::SurfaceMapping::new(99) }
+
+    it "can encode every section into one polyline" do
+      sections = [Tracklib::Section::standard(schema, first_data),
+                  Tracklib::Section::standard(schema, second_data)]
+      reader = Tracklib::TrackReader::new(Tracklib::write_track([], sections))
+
+      polyline = TracklibRwgps::track_data_polyline(reader, TracklibRwgps::PolylineOptions::new([["y", 5], ["x", 5], ["d", 5]]))
+      expect(decode_polyline(polyline, [5, 5, 5]))
+        .to eq([72.1, -122.402, 0.0,
+                72.309, -122.5, 23477.14945,
+                72.1, -122.402, 46954.29891])
+    end
+
+    it "can simplify every section" do
+      sections = [Tracklib::Section::standard(schema, first_data),
+                  Tracklib::Section::standard(schema, second_data)]
+      reader = Tracklib::TrackReader::new(Tracklib::write_track([], sections))
+
+      simplified = TracklibRwgps::track_data_simplified(reader, surface_mapping, 0.0)
+      expect(simplified.map { |row| row.reject { |column, _| column == "d" } })
+        .to eq(first_data + second_data)
+      expect(simplified.map { |row| row["d"] })
+        .to match([0.0, be_within(0.001).of(23477.14945), be_within(0.001).of(46954.29891)])
+    end
+
+    it "replaces a section's own d column with the distance along the track" do
+      d_schema = Tracklib::Schema.new([["x", :f64, 6], ["y", :f64, 6], ["d", :f64, 1]])
+      sections = [Tracklib::Section::standard(d_schema, [{"x" => -122.402, "y" => 72.1, "d" => 5.0},
+                                                         {"x" => -122.500, "y" => 72.309, "d" => 6.0}])]
+      reader = Tracklib::TrackReader::new(Tracklib::write_track([], sections))
+
+      expect(TracklibRwgps::track_data_simplified(reader, surface_mapping, 0.0).map { |row| row["d"] })
+        .to match([0.0, be_within(0.001).of(23477.14945)])
+    end
+
+    it "accepts key material per section" do
+      sections = [Tracklib::Section::standard(schema, first_data),
+                  Tracklib::Section::encrypted(schema, second_data, key)]
+      reader = Tracklib::TrackReader::new(Tracklib::write_track([], sections))
+
+      expect(TracklibRwgps::track_data_simplified(reader, surface_mapping, 0.0, [nil, key])
+               .map { |row| row.reject { |column, _| column == "d" } })
+        .to eq(first_data + second_data)
+      expect { TracklibRwgps::track_data_simplified(reader, surface_mapping, 0.0) }
+        .to raise_error(TracklibRwgps::KeyMaterialError)
+    end
+
+    it "rejects key material that isn't a String or nil" do
+      sections = [Tracklib::Section::standard(schema, first_data),
+                  Tracklib::Section::encrypted(schema, second_data, key)]
+      reader = Tracklib::TrackReader::new(Tracklib::write_track([], sections))
+
+      expect { TracklibRwgps::track_data_simplified(reader, surface_mapping, 0.0, [nil, 42]) }
+        .to raise_error(TypeError)
+      expect { TracklibRwgps::track_data_polyline(reader, TracklibRwgps::PolylineOptions::new([["y", 5]]), 42) }
+        .to raise_error(TypeError)
+    end
+  end
+end
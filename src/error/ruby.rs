@@ -0,0 +1,28 @@
+use super::rust::TracklibRwgpsError;
+use rutie::{Module, VM};
+
+fn ruby_class_name(error: &TracklibRwgpsError) -> &'static str {
+    match error {
+        TracklibRwgpsError::SectionMissing => "SectionMissingError",
+        TracklibRwgpsError::SchemaMismatch(_) | TracklibRwgpsError::MissingColumn(_) => "SchemaMismatchError",
+        TracklibRwgpsError::MissingKeyMaterial | TracklibRwgpsError::KeyMaterial(_) => "KeyMaterialError",
+        TracklibRwgpsError::InvalidPolylineOption(_) => "PolylineOptionError",
+        TracklibRwgpsError::InvalidSimplifyOption(_) => "SimplifyOptionError",
+        TracklibRwgpsError::InvalidBbox(_) => "BboxError",
+        TracklibRwgpsError::InvalidRegion(_) => "RegionError",
+        TracklibRwgpsError::InvalidSurfaceMapping(_) => "SurfaceMappingError",
+        TracklibRwgpsError::InvalidPointSchema(_) => "PointSchemaError",
+        TracklibRwgpsError::InvalidExportOption(_) => "ExportOptionError",
+        TracklibRwgpsError::InvalidStatsOption(_) => "StatsOptionError",
+        TracklibRwgpsError::InvalidPolyline(_)
+        | TracklibRwgpsError::Decode(_)
+        | TracklibRwgpsError::RowCountMismatch(..) => "DecodeError",
+    }
+}
+
+pub(crate) fn raise(error: TracklibRwgpsError) {
+    VM::raise(
+        Module::from_existing("TracklibRwgps").get_nested_class(ruby_class_name(&error)),
+        &error.to_string(),
+    );
+}
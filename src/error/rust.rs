@@ -0,0 +1,38 @@
+use thiserror::Error;
+use tracklib::error::TracklibError;
+
+#[derive(Error, Debug)]
+pub enum TracklibRwgpsError {
+    #[error("Section does not exist")]
+    SectionMissing,
+    #[error("Section schema mismatch: {0}")]
+    SchemaMismatch(#[source] TracklibError),
+    #[error("Section schema mismatch: column '{0}' is missing from the row")]
+    MissingColumn(String),
+    #[error("Key material is required to read an encrypted section")]
+    MissingKeyMaterial,
+    #[error("Unable to decrypt section with the given key material: {0}")]
+    KeyMaterial(#[source] TracklibError),
+    #[error("{0}")]
+    InvalidPolylineOption(String),
+    #[error("{0}")]
+    InvalidSimplifyOption(String),
+    #[error("{0}")]
+    InvalidBbox(String),
+    #[error("{0}")]
+    InvalidRegion(String),
+    #[error("{0}")]
+    InvalidSurfaceMapping(String),
+    #[error("{0}")]
+    InvalidPointSchema(String),
+    #[error("{0}")]
+    InvalidExportOption(String),
+    #[error("{0}")]
+    InvalidStatsOption(String),
+    #[error("Invalid polyline: {0}")]
+    InvalidPolyline(String),
+    #[error("Error decoding section data: {0}")]
+    Decode(#[source] TracklibError),
+    #[error("Error decoding section data: read {0} rows for {1} points")]
+    RowCountMismatch(usize, usize),
+}
@@ -0,0 +1,4 @@
+#[cfg(feature = "ruby")]
+pub(crate) mod ruby;
+#[cfg(feature = "serde")]
+pub(crate) mod rust;
@@ -0,0 +1,83 @@
+use super::rust::geojson_encode;
+use crate::error::ruby::raise;
+use crate::error::rust::TracklibRwgpsError;
+use crate::geometry::{section_to_points, IrrelevantPointsBehavior};
+use crate::ruby::key_material_from_ruby;
+use crate::schema::ruby::RubyPointSchema;
+use crate::schema::rust::PointSchema;
+use crate::simplify::ruby::simplify_options_from_ruby;
+use crate::simplify::rust::simplify_points;
+use crate::surface::ruby::RubySurfaceMapping;
+use itertools::Itertools;
+use rutie::{methods, module, AnyObject, Boolean, Integer, Object, RString, VM};
+
+module!(TracklibRwgps);
+
+methods!(
+    TracklibRwgps,
+    _rtself,
+    fn geojson_section_data_geojson(
+        track_reader: ruby_tracklib::read::TrackReader,
+        index: Integer,
+        mapping: RubySurfaceMapping,
+        tolerance: AnyObject,
+        split_by_surface: Boolean,
+        key_material: AnyObject,
+        point_schema: RubyPointSchema) -> RString {
+        let ruby_track_reader = track_reader.map_err(VM::raise_ex).unwrap();
+
+        let rust_simplify_opts = simplify_options_from_ruby(tolerance.map_err(VM::raise_ex).unwrap());
+
+        let ruby_index = index.map_err(VM::raise_ex).unwrap();
+        let rust_index = usize::try_from(ruby_index.to_u64())
+            .map_err(|_| raise(TracklibRwgpsError::SectionMissing))
+            .unwrap();
+
+        let ruby_mapping = mapping.map_err(VM::raise_ex).unwrap();
+        let rust_mapping = ruby_mapping.inner();
+
+        let rust_split_by_surface = split_by_surface.is_ok_and(|split| split.to_bool());
+
+        let ruby_key_material = key_material.ok().and_then(key_material_from_ruby);
+        let rust_key_material = ruby_key_material.as_ref().map(|key| key.to_bytes_unchecked());
+
+        let rust_point_schema = point_schema
+            .ok()
+            .map_or_else(PointSchema::new, |ruby_point_schema| ruby_point_schema.inner().clone());
+
+        ruby_track_reader.with_track_reader(|track_reader| {
+            let mut section = track_reader
+                .section(rust_index)
+                .ok_or(TracklibRwgpsError::SectionMissing)
+                .map_err(raise)
+                .unwrap();
+            let points = section_to_points(
+                &mut section,
+                rust_key_material,
+                &rust_point_schema,
+                None,
+                IrrelevantPointsBehavior::Ignore,
+            )
+            .map_err(raise)
+            .unwrap();
+            let simplified_indexes = simplify_points(&points, &rust_mapping, &rust_simplify_opts);
+            let simplified_points = simplified_indexes
+                .into_iter()
+                .sorted()
+                .map(|index| points[index].clone())
+                .collect::<Vec<_>>();
+
+            let geojson_mapping = if rust_split_by_surface {
+                Some(rust_mapping)
+            } else {
+                None
+            };
+
+            RString::from(geojson_encode(
+                &simplified_points,
+                geojson_mapping,
+                rust_simplify_opts.surface_smoothing(),
+            ))
+        })
+    }
+);
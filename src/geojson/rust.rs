@@ -0,0 +1,176 @@
+use crate::geometry::Point;
+use crate::simplify::rust::{surface_group_lines, SurfaceSmoothing};
+use crate::surface::rust::SurfaceMapping;
+use serde::Serialize;
+
+// GeoJSON objects carry their kind in a leading "type" member, which serde writes from the struct's name. serde_json
+// writes NaN and infinite coordinates as null, as JSON has neither.
+#[derive(Serialize)]
+#[serde(tag = "type")]
+struct FeatureCollection<'b> {
+    features: Vec<Feature<MultiLineString, MultiLineProperties<'b>>>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type")]
+struct Feature<G, P> {
+    geometry: G,
+    properties: P,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type")]
+struct LineString {
+    coordinates: Vec<[f64; 2]>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type")]
+struct MultiLineString {
+    coordinates: Vec<Vec<[f64; 2]>>,
+}
+
+#[derive(Serialize)]
+struct LineProperties {
+    distance: Vec<f64>,
+    elevation: Vec<f64>,
+}
+
+#[derive(Serialize)]
+struct MultiLineProperties<'b> {
+    surface: Option<&'b String>,
+    distance: Vec<Vec<f64>>,
+    elevation: Vec<Vec<f64>>,
+}
+
+fn coordinates(line: &[Point]) -> Vec<[f64; 2]> {
+    line.iter().map(|point| [point.x(), point.y()]).collect()
+}
+
+fn values(line: &[Point], value: fn(&Point) -> f64) -> Vec<f64> {
+    line.iter().map(value).collect()
+}
+
+fn line_feature(line: &[Point]) -> Feature<LineString, LineProperties> {
+    Feature {
+        geometry: LineString {
+            coordinates: coordinates(line),
+        },
+        properties: LineProperties {
+            distance: values(line, Point::d),
+            elevation: values(line, Point::e),
+        },
+    }
+}
+
+fn multi_line_feature<'b>(
+    group: Option<&'b String>,
+    lines: &[&[Point]],
+) -> Feature<MultiLineString, MultiLineProperties<'b>> {
+    Feature {
+        geometry: MultiLineString {
+            coordinates: lines.iter().map(|line| coordinates(line)).collect(),
+        },
+        properties: MultiLineProperties {
+            surface: group,
+            distance: lines.iter().map(|line| values(line, Point::d)).collect(),
+            elevation: lines.iter().map(|line| values(line, Point::e)).collect(),
+        },
+    }
+}
+
+// Without a mapping this is a single LineString Feature. With one it's a FeatureCollection holding a MultiLineString
+// Feature per surface group, in the order the groups first appear along the track. Groups are smoothed with
+// `smoothing`, which should match the smoothing the points were simplified with.
+pub fn geojson_encode(points: &[Point], mapping: Option<&SurfaceMapping>, smoothing: SurfaceSmoothing) -> String {
+    // None of these types hold a map, so serde_json has nothing it can fail to write
+    match mapping {
+        None => serde_json::to_string(&line_feature(points)).unwrap(),
+        Some(mapping) => {
+            let mut groups: Vec<(Option<&String>, Vec<&[Point]>)> = Vec::new();
+
+            for (group, line) in surface_group_lines(points, mapping, smoothing) {
+                match groups.iter_mut().find(|(existing_group, _)| *existing_group == group) {
+                    Some((_, lines)) => lines.push(line),
+                    None => groups.push((group, vec![line])),
+                }
+            }
+
+            let collection = FeatureCollection {
+                features: groups
+                    .iter()
+                    .map(|(group, lines)| multi_line_feature(*group, lines))
+                    .collect(),
+            };
+            serde_json::to_string(&collection).unwrap()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_feature() {
+        let points = vec![
+            Point::new(0, -122.5, 45.25, 0.0, 10.0, None, None),
+            Point::new(1, -122.0, 45.5, 50.5, 12.5, None, None),
+        ];
+
+        assert_eq!(
+            geojson_encode(&points, None, SurfaceSmoothing::None),
+            r#"{"type":"Feature","geometry":{"type":"LineString","coordinates":[[-122.5,45.25],[-122.0,45.5]]},"properties":{"distance":[0.0,50.5],"elevation":[10.0,12.5]}}"#
+        );
+    }
+
+    #[test]
+    fn test_features_per_surface_group() {
+        let mut mapping = SurfaceMapping::new(99);
+        mapping.add_surface(1, "Paved".to_string());
+        mapping.add_surface(2, "Gravel \"loose\"".to_string());
+
+        let points = vec![
+            Point::new(0, 0.0, 0.0, 0.0, 1.0, Some(1), None),
+            Point::new(1, 1.0, 0.0, 1.0, 2.0, Some(2), None),
+            Point::new(2, 2.0, 0.0, 2.0, 3.0, Some(1), None),
+            Point::new(3, 3.0, 0.0, 3.0, 4.0, Some(1), None),
+        ];
+
+        assert_eq!(
+            geojson_encode(&points, Some(&mapping), SurfaceSmoothing::None),
+            concat!(
+                r#"{"type":"FeatureCollection","features":["#,
+                r#"{"type":"Feature","geometry":{"type":"MultiLineString","coordinates":[[[0.0,0.0],[1.0,0.0]],[[2.0,0.0],[3.0,0.0]]]},"#,
+                r#""properties":{"surface":"Paved","distance":[[0.0,1.0],[2.0,3.0]],"elevation":[[1.0,2.0],[3.0,4.0]]}},"#,
+                r#"{"type":"Feature","geometry":{"type":"MultiLineString","coordinates":[[[1.0,0.0],[2.0,0.0]]]},"#,
+                r#""properties":{"surface":"Gravel \"loose\"","distance":[[1.0,2.0]],"elevation":[[2.0,3.0]]}}"#,
+                r#"]}"#
+            )
+        );
+
+        // Smoothing merges the single Gravel point into the Paved around it, as it would when simplifying
+        assert_eq!(
+            geojson_encode(&points, Some(&mapping), SurfaceSmoothing::Majority(3)),
+            concat!(
+                r#"{"type":"FeatureCollection","features":["#,
+                r#"{"type":"Feature","geometry":{"type":"MultiLineString","coordinates":[[[0.0,0.0],[1.0,0.0],[2.0,0.0],[3.0,0.0]]]},"#,
+                r#""properties":{"surface":"Paved","distance":[[0.0,1.0,2.0,3.0]],"elevation":[[1.0,2.0,3.0,4.0]]}}"#,
+                r#"]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_non_finite_values_are_null() {
+        let points = vec![
+            Point::new(0, 0.0, 0.0, 0.0, f64::NAN, None, None),
+            Point::new(1, 1.0, 0.0, f64::INFINITY, 1.0, None, None),
+        ];
+
+        assert_eq!(
+            geojson_encode(&points, None, SurfaceSmoothing::None),
+            r#"{"type":"Feature","geometry":{"type":"LineString","coordinates":[[0.0,0.0],[1.0,0.0]]},"properties":{"distance":[0.0,null],"elevation":[null,1.0]}}"#
+        );
+    }
+}
@@ -1,8 +1,13 @@
+use crate::error::rust::TracklibRwgpsError;
+use crate::schema::rust::PointSchema;
 use crate::surface::rust::{RoadClassId, SurfaceTypeId};
-use rutie::{Class, VM};
+use tracklib::read::section::reader::SectionReader;
+use tracklib::read::section::Section;
+use tracklib::schema::Schema;
+use tracklib::types::FieldValue;
 
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct Point {
+pub struct Point {
     index: usize,
     x: f64,
     y: f64,
@@ -10,18 +15,12 @@ pub(crate) struct Point {
     e: f64,
     s: Option<SurfaceTypeId>,
     r: Option<RoadClassId>,
+    t: Option<i64>,
+    speed: Option<f64>,
 }
 
 impl Point {
-    pub(crate) fn new(
-        index: usize,
-        x: f64,
-        y: f64,
-        d: f64,
-        e: f64,
-        s: Option<SurfaceTypeId>,
-        r: Option<RoadClassId>,
-    ) -> Self {
+    pub fn new(index: usize, x: f64, y: f64, d: f64, e: f64, s: Option<SurfaceTypeId>, r: Option<RoadClassId>) -> Self {
         Self {
             index,
             x,
@@ -30,36 +29,51 @@ impl Point {
             e,
             s,
             r,
+            t: None,
+            speed: None,
         }
     }
 
-    pub(crate) fn index(&self) -> usize {
+    pub fn with_time(self, t: Option<i64>, speed: Option<f64>) -> Self {
+        Self { t, speed, ..self }
+    }
+
+    pub fn index(&self) -> usize {
         self.index
     }
 
-    pub(crate) fn x(&self) -> f64 {
+    pub fn x(&self) -> f64 {
         self.x
     }
 
-    pub(crate) fn y(&self) -> f64 {
+    pub fn y(&self) -> f64 {
         self.y
     }
 
-    pub(crate) fn d(&self) -> f64 {
+    pub fn d(&self) -> f64 {
         self.d
     }
 
-    pub(crate) fn e(&self) -> f64 {
+    pub fn e(&self) -> f64 {
         self.e
     }
 
-    pub(crate) fn s(&self) -> Option<SurfaceTypeId> {
+    pub fn s(&self) -> Option<SurfaceTypeId> {
         self.s
     }
 
-    pub(crate) fn r(&self) -> Option<RoadClassId> {
+    pub fn r(&self) -> Option<RoadClassId> {
         self.r
     }
+
+    pub fn t(&self) -> Option<i64> {
+        self.t
+    }
+
+    // Meters per second since the previous point, when both have a time
+    pub fn speed(&self) -> Option<f64> {
+        self.speed
+    }
 }
 
 impl Default for Point {
@@ -72,34 +86,49 @@ impl Default for Point {
             e: 0.0,
             s: Some(0),
             r: Some(0),
+            t: None,
+            speed: None,
         }
     }
 }
 
 pub(crate) trait FarthestPoint {
     fn farthest_point(&self) -> (usize, f64);
+    fn farthest_point_3d(&self) -> (usize, f64);
 }
 
 impl FarthestPoint for &[Point] {
     fn farthest_point(&self) -> (usize, f64) {
-        let line = Line::new(self.first().unwrap(), self.last().unwrap());
-
-        self.iter()
-            .enumerate()
-            .take(self.len() - 1) // Don't include the last index
-            .skip(1) // Don't include the first index
-            .map(|(index, point)| (index, line.distance_2d(&point)))
-            .fold(
-                (0, 0.0),
-                |(farthest_index, farthest_dist), (index, distance)| {
-                    if distance > farthest_dist {
-                        (index, distance)
-                    } else {
-                        (farthest_index, farthest_dist)
-                    }
-                },
-            )
+        farthest_point_by(self, |line, point| line.distance_2d(point))
     }
+
+    fn farthest_point_3d(&self) -> (usize, f64) {
+        farthest_point_by(self, |line, point| line.distance_3d(point))
+    }
+}
+
+fn farthest_point_by<F>(points: &[Point], distance: F) -> (usize, f64)
+where
+    F: Fn(&Line, &Point) -> f64,
+{
+    let line = Line::new(points.first().unwrap(), points.last().unwrap());
+
+    points
+        .iter()
+        .enumerate()
+        .take(points.len() - 1) // Don't include the last index
+        .skip(1) // Don't include the first index
+        .map(|(index, point)| (index, distance(&line, point)))
+        .fold(
+            (0, 0.0),
+            |(farthest_index, farthest_dist), (index, distance)| {
+                if distance > farthest_dist {
+                    (index, distance)
+                } else {
+                    (farthest_index, farthest_dist)
+                }
+            },
+        )
 }
 
 struct Line<'a> {
@@ -134,14 +163,60 @@ impl<'a> Line<'a> {
         dx = point.x - x;
         dy = point.y - y;
 
-        return dx * dx + dy * dy;
+        dx * dx + dy * dy
+    }
+
+    // Same as distance_2d with elevation as the third axis, so x and y must already be in meters
+    fn distance_3d(&self, point: &Point) -> f64 {
+        let mut x = self.start.x;
+        let mut y = self.start.y;
+        let mut z = self.start.e;
+
+        let mut dx = self.end.x - x;
+        let mut dy = self.end.y - y;
+        let mut dz = self.end.e - z;
+
+        if dx != 0.0 || dy != 0.0 || dz != 0.0 {
+            let t = ((point.x - x) * dx + (point.y - y) * dy + (point.e - z) * dz) / (dx * dx + dy * dy + dz * dz);
+
+            if t > 1.0 {
+                x = self.end.x;
+                y = self.end.y;
+                z = self.end.e;
+            } else if t > 0.0 {
+                x += dx * t;
+                y += dy * t;
+                z += dz * t;
+            }
+        }
+
+        dx = point.x - x;
+        dy = point.y - y;
+        dz = point.e - z;
+
+        dx * dx + dy * dy + dz * dz
     }
 }
 
+pub(crate) fn triangle_area_2d(a: &Point, b: &Point, c: &Point) -> f64 {
+    ((b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y)).abs() / 2.0
+}
+
+pub(crate) fn triangle_area_3d(a: &Point, b: &Point, c: &Point) -> f64 {
+    let (abx, aby, abz) = (b.x - a.x, b.y - a.y, b.e - a.e);
+    let (acx, acy, acz) = (c.x - a.x, c.y - a.y, c.e - a.e);
+
+    let cross_x = aby * acz - abz * acy;
+    let cross_y = abz * acx - abx * acz;
+    let cross_z = abx * acy - aby * acx;
+
+    (cross_x * cross_x + cross_y * cross_y + cross_z * cross_z).sqrt() / 2.0
+}
+
+const MEAN_EARTH_RADIUS: f64 = 6_371_000.0;
+
 pub(crate) fn haversine_distance(prev: &Point, x: f64, y: f64) -> f64 {
     // lifted wholesale from https://github.com/georust/geo/blob/2cf153d59072d18054baf4da8bcaf3e0c088a7d8/geo/src/algorithm/haversine_distance.rs
-    const MEAN_EARTH_RADIUS: f64 = 6_371_000.0;
-
     let theta1 = prev.y.to_radians();
     let theta2 = y.to_radians();
     let delta_theta = (y - prev.y).to_radians();
@@ -151,92 +226,429 @@ pub(crate) fn haversine_distance(prev: &Point, x: f64, y: f64) -> f64 {
     MEAN_EARTH_RADIUS * c
 }
 
-use std::collections::HashMap;
+// Project lon/lat degrees into meters with an equirectangular projection centered on these points. Over the extent of
+// a single track this is accurate enough to compare distances against a tolerance in meters.
+pub(crate) fn project_to_meters(points: &[Point]) -> Vec<Point> {
+    let (min_y, max_y) = points
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min_y, max_y), point| {
+            (min_y.min(point.y), max_y.max(point.y))
+        });
+    let origin_x = points.first().map(|point| point.x).unwrap_or(0.0);
+    let x_scale = ((min_y + max_y) / 2.0).to_radians().cos() * MEAN_EARTH_RADIUS;
 
-fn new_point<'a, T>(index: usize, prev: Option<&Point>, iter: T) -> Option<Point>
-where
-    T: IntoIterator<
-        Item = (
-            &'a tracklib::schema::FieldDefinition,
-            Option<tracklib::types::FieldValue>,
-        ),
-    >,
-{
-    let fields = iter
-        .into_iter()
-        .map(|(field_def, field_value)| (field_def.name(), field_value))
-        .collect::<HashMap<_, _>>();
-
-    if let Some((x, y, e, d)) = match (fields.get("x"), fields.get("y"), fields.get("e")) {
-        (
-            Some(Some(tracklib::types::FieldValue::F64(x))),
-            Some(Some(tracklib::types::FieldValue::F64(y))),
-            Some(Some(tracklib::types::FieldValue::F64(e))),
-        ) => {
-            let d = if let Some(p) = prev {
-                p.d() + haversine_distance(p, *x, *y)
-            } else {
-                0.0
-            };
-
-            Some((*x, *y, *e, d))
-        }
-        _ => None,
-    } {
-        let mut s = None;
-        let mut r = None;
-
-        match fields.get("S") {
-            Some(Some(tracklib::types::FieldValue::U64(v))) => s = Some(*v),
-            None | Some(None) => {}
-            _ => {
-                return None;
+    points
+        .iter()
+        .map(|point| {
+            // Keep tracks that cross the antimeridian continuous
+            let dx = (point.x - origin_x + 540.0).rem_euclid(360.0) - 180.0;
+
+            Point {
+                x: dx.to_radians() * x_scale,
+                y: point.y.to_radians() * MEAN_EARTH_RADIUS,
+                ..point.clone()
             }
+        })
+        .collect()
+}
+
+// Lay points out as an elevation profile: distance along the track on x and elevation on y, both in meters
+pub(crate) fn project_to_profile(points: &[Point]) -> Vec<Point> {
+    points
+        .iter()
+        .map(|point| Point {
+            x: point.d,
+            y: point.e,
+            ..point.clone()
+        })
+        .collect()
+}
+
+// Positions of the point's fields within a row. Every row of a section has the same columns in the same order, so these
+// are resolved once from the first row instead of looking fields up by name on every row.
+#[derive(Clone, Copy, Debug, Default)]
+struct PointColumns {
+    x: Option<usize>,
+    y: Option<usize>,
+    e: Option<usize>,
+    s: Option<usize>,
+    r: Option<usize>,
+    t: Option<usize>,
+}
+
+impl PointColumns {
+    fn resolve(&mut self, position: usize, name: &str, point_schema: &PointSchema) {
+        for (column, field_name) in [
+            (&mut self.x, point_schema.x()),
+            (&mut self.y, point_schema.y()),
+            (&mut self.e, point_schema.e()),
+            (&mut self.s, point_schema.s()),
+            (&mut self.r, point_schema.r()),
+        ] {
+            if name == field_name {
+                *column = Some(position);
+            }
+        }
+        if point_schema.t() == Some(name) {
+            self.t = Some(position);
         }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PointValues {
+    x: Option<FieldValue>,
+    y: Option<FieldValue>,
+    e: Option<FieldValue>,
+    s: Option<FieldValue>,
+    r: Option<FieldValue>,
+    t: Option<FieldValue>,
+}
 
-        match fields.get("R") {
-            Some(Some(tracklib::types::FieldValue::U64(v))) => r = Some(*v),
-            None | Some(None) => {}
-            _ => {
-                return None;
+impl PointValues {
+    fn store(&mut self, columns: &PointColumns, position: usize, value: Option<FieldValue>) {
+        let Some(value) = value else {
+            return;
+        };
+        for (column, slot) in [
+            (columns.x, &mut self.x),
+            (columns.y, &mut self.y),
+            (columns.e, &mut self.e),
+            (columns.s, &mut self.s),
+            (columns.r, &mut self.r),
+            (columns.t, &mut self.t),
+        ] {
+            if column == Some(position) {
+                *slot = Some(value.clone());
             }
         }
+    }
+}
 
-        Some(Point::new(index, x, y, d, e, s, r))
+fn new_point(index: usize, prev: Option<&Point>, values: PointValues) -> Option<Point> {
+    let (x, y, e) = match (values.x, values.y, values.e) {
+        (Some(FieldValue::F64(x)), Some(FieldValue::F64(y)), Some(FieldValue::F64(e))) => (x, y, e),
+        _ => return None,
+    };
+    let d = if let Some(p) = prev {
+        p.d() + haversine_distance(p, x, y)
     } else {
-        None
-    }
+        0.0
+    };
+
+    let s = match values.s {
+        Some(FieldValue::U64(v)) => Some(v),
+        None => None,
+        _ => return None,
+    };
+
+    let r = match values.r {
+        Some(FieldValue::U64(v)) => Some(v),
+        None => None,
+        _ => return None,
+    };
+
+    let t = match values.t {
+        Some(FieldValue::I64(v)) => Some(v),
+        None => None,
+        _ => return None,
+    };
+
+    let speed = match (prev.and_then(|p| p.t().map(|prev_t| (p.d(), prev_t))), t) {
+        (Some((prev_d, prev_t)), Some(t)) if t > prev_t => Some((d - prev_d) / (t - prev_t) as f64),
+        _ => None,
+    };
+
+    Some(Point::new(index, x, y, d, e, s, r).with_time(t, speed))
 }
 
-#[derive(PartialEq)]
-pub(crate) enum IrrelevantPointsBehavior {
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IrrelevantPointsBehavior {
     Count,
     Ignore,
 }
 
-pub(crate) fn reader_to_points(
-    mut reader: tracklib::read::section::reader::SectionReader,
+// Reads points from a section one row at a time, so a whole section never has to be held in memory
+pub struct PointReader<'a, 'b, 'p> {
+    reader: SectionReader<'a, 'b>,
+    point_schema: &'p PointSchema,
+    columns: Option<PointColumns>,
+    prev: Option<Point>,
+    index: usize,
     irrelevant_points_behavior: IrrelevantPointsBehavior,
-) -> Vec<Point> {
-    let mut index = 0;
-    let mut points = Vec::with_capacity(reader.rows_remaining());
-    while let Some(columniter) = reader.open_column_iter() {
-        if let Some(point) = new_point(
-            index,
-            points.last(),
-            columniter.map(|row| {
-                row.map_err(|e| VM::raise(Class::from_existing("Exception"), &format!("{}", e)))
-                    .unwrap()
-            }),
-        ) {
-            points.push(point);
-            index += 1;
-        } else {
-            if irrelevant_points_behavior == IrrelevantPointsBehavior::Count {
-                index += 1;
+}
+
+impl<'a, 'b, 'p> PointReader<'a, 'b, 'p> {
+    pub fn new(
+        reader: SectionReader<'a, 'b>,
+        point_schema: &'p PointSchema,
+        previous_point: Option<&Point>,
+        irrelevant_points_behavior: IrrelevantPointsBehavior,
+    ) -> Self {
+        Self {
+            reader,
+            point_schema,
+            columns: None,
+            prev: previous_point.cloned(),
+            index: 0,
+            irrelevant_points_behavior,
+        }
+    }
+
+    pub fn rows_remaining(&self) -> usize {
+        self.reader.rows_remaining()
+    }
+}
+
+impl Iterator for PointReader<'_, '_, '_> {
+    type Item = Result<Point, TracklibRwgpsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let column_iter = self.reader.open_column_iter()?;
+            let resolve = self.columns.is_none();
+            let columns = self.columns.get_or_insert_with(PointColumns::default);
+
+            let mut values = PointValues::default();
+            for (position, column) in column_iter.enumerate() {
+                let (field_def, value) = match column {
+                    Ok(column) => column,
+                    Err(e) => return Some(Err(TracklibRwgpsError::Decode(e))),
+                };
+                if resolve {
+                    columns.resolve(position, field_def.name(), self.point_schema);
+                }
+                values.store(columns, position, value);
+            }
+
+            if let Some(point) = new_point(self.index, self.prev.as_ref(), values) {
+                self.prev = Some(point.clone());
+                self.index += 1;
+                return Some(Ok(point));
+            } else if self.irrelevant_points_behavior == IrrelevantPointsBehavior::Count {
+                self.index += 1;
             }
         }
     }
+}
 
-    points
+pub fn reader_to_points(
+    reader: SectionReader,
+    point_schema: &PointSchema,
+    previous_point: Option<&Point>,
+    irrelevant_points_behavior: IrrelevantPointsBehavior,
+) -> Result<Vec<Point>, TracklibRwgpsError> {
+    let point_reader = PointReader::new(reader, point_schema, previous_point, irrelevant_points_behavior);
+    let mut points = Vec::with_capacity(point_reader.rows_remaining());
+    for point in point_reader {
+        points.push(point?);
+    }
+
+    Ok(points)
+}
+
+pub fn with_section_reader<F, R>(
+    section: &mut Section,
+    schema: Option<&Schema>,
+    key_material: Option<&[u8]>,
+    f: F,
+) -> Result<R, TracklibRwgpsError>
+where
+    F: FnOnce(tracklib::read::section::reader::SectionReader) -> Result<R, TracklibRwgpsError>,
+{
+    let reader = match section {
+        Section::Standard(section) => match schema {
+            Some(schema) => section
+                .reader_for_schema(schema)
+                .map_err(TracklibRwgpsError::SchemaMismatch)?,
+            None => section.reader().map_err(TracklibRwgpsError::Decode)?,
+        },
+        Section::Encrypted(section) => {
+            let key_material = key_material.ok_or(TracklibRwgpsError::MissingKeyMaterial)?;
+            match schema {
+                Some(schema) => section.reader_for_schema(key_material, schema),
+                None => section.reader(key_material),
+            }
+            .map_err(TracklibRwgpsError::KeyMaterial)?
+        }
+    };
+
+    f(reader)
+}
+
+// Like section_to_points, but hands `f` a PointReader instead of collecting every point first
+pub fn with_section_points<F, R>(
+    section: &mut Section,
+    key_material: Option<&[u8]>,
+    point_schema: &PointSchema,
+    previous_point: Option<&Point>,
+    irrelevant_points_behavior: IrrelevantPointsBehavior,
+    f: F,
+) -> Result<R, TracklibRwgpsError>
+where
+    F: FnOnce(PointReader) -> Result<R, TracklibRwgpsError>,
+{
+    let schema = point_schema.schema();
+    with_section_reader(section, Some(&schema), key_material, |reader| {
+        f(PointReader::new(
+            reader,
+            point_schema,
+            previous_point,
+            irrelevant_points_behavior,
+        ))
+    })
+}
+
+pub fn section_to_points(
+    section: &mut Section,
+    key_material: Option<&[u8]>,
+    point_schema: &PointSchema,
+    previous_point: Option<&Point>,
+    irrelevant_points_behavior: IrrelevantPointsBehavior,
+) -> Result<Vec<Point>, TracklibRwgpsError> {
+    let schema = point_schema.schema();
+    with_section_reader(section, Some(&schema), key_material, |reader| {
+        reader_to_points(reader, point_schema, previous_point, irrelevant_points_behavior)
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use tracklib::schema::{DataType, FieldDefinition};
+    use tracklib::types::FieldValue;
+
+    // Small deterministic LCG so tests don't need a rand dependency, yields values in [0, 1)
+    pub(crate) fn lcg(seed: u64) -> impl FnMut() -> f64 {
+        let mut state = seed;
+        move || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1_u64 << 53) as f64
+        }
+    }
+
+    // A random walk heading roughly east with random elevations, switching between surfaces 1 and 2 every 75 points
+    pub(crate) fn random_walk(seed: u64, n: usize) -> Vec<Point> {
+        let mut next = lcg(seed);
+        let mut x = 0.0;
+        let mut y = 0.0;
+        (0..n)
+            .map(|i| {
+                x += next() * 0.001;
+                y += (next() - 0.5) * 0.001;
+                let e = next() * 100.0;
+                Point::new(i, x, y, 0.0, e, Some(1 + (i as u64 / 75) % 2), None)
+            })
+            .collect()
+    }
+
+    fn row_point<'a>(
+        index: usize,
+        prev: Option<&Point>,
+        point_schema: &PointSchema,
+        row: impl IntoIterator<Item = (&'a FieldDefinition, Option<FieldValue>)>,
+    ) -> Option<Point> {
+        let mut columns = PointColumns::default();
+        let mut values = PointValues::default();
+        for (position, (field_def, value)) in row.into_iter().enumerate() {
+            columns.resolve(position, field_def.name(), point_schema);
+            values.store(&columns, position, value);
+        }
+        new_point(index, prev, values)
+    }
+
+    #[test]
+    fn test_new_point_speed() {
+        let point_schema = PointSchema::with_columns([("t", "t", None)]).unwrap();
+        let fields = [
+            FieldDefinition::new("x", DataType::F64 { scale: 6 }),
+            FieldDefinition::new("y", DataType::F64 { scale: 6 }),
+            FieldDefinition::new("e", DataType::F64 { scale: 1 }),
+            FieldDefinition::new("t", DataType::I64),
+        ];
+        let row = |y: f64, t: Option<i64>| {
+            fields.iter().zip([
+                Some(FieldValue::F64(0.0)),
+                Some(FieldValue::F64(y)),
+                Some(FieldValue::F64(0.0)),
+                t.map(FieldValue::I64),
+            ])
+        };
+
+        let first = row_point(0, None, &point_schema, row(0.0, Some(100))).unwrap();
+        assert_eq!(first.t(), Some(100));
+        assert_eq!(first.speed(), None);
+
+        // 0.001 degrees of latitude is ~111m, covered in 10 seconds
+        let second = row_point(1, Some(&first), &point_schema, row(0.001, Some(110))).unwrap();
+        assert_eq!(second.t(), Some(110));
+        assert!((second.speed().unwrap() - 11.1195).abs() < 0.001);
+
+        let untimed = row_point(2, Some(&second), &point_schema, row(0.002, None)).unwrap();
+        assert_eq!(untimed.t(), None);
+        assert_eq!(untimed.speed(), None);
+    }
+
+    #[test]
+    fn test_point_columns() {
+        let point_schema = PointSchema::with_columns([("x", "lng", None), ("S", "surface", None)]).unwrap();
+
+        let fields = [
+            FieldDefinition::new("surface", DataType::U64),
+            FieldDefinition::new("e", DataType::F64 { scale: 1 }),
+            FieldDefinition::new("name", DataType::String),
+            FieldDefinition::new("y", DataType::F64 { scale: 6 }),
+            FieldDefinition::new("lng", DataType::F64 { scale: 6 }),
+        ];
+        let mut columns = PointColumns::default();
+        for (position, field_def) in fields.iter().enumerate() {
+            columns.resolve(position, field_def.name(), &point_schema);
+        }
+        assert_eq!(
+            (columns.x, columns.y, columns.e, columns.s, columns.r, columns.t),
+            (Some(4), Some(3), Some(1), Some(0), None, None)
+        );
+
+        let read = |row: [Option<FieldValue>; 5]| {
+            let mut values = PointValues::default();
+            for (position, value) in row.into_iter().enumerate() {
+                values.store(&columns, position, value);
+            }
+            new_point(7, None, values)
+        };
+
+        let point = read([
+            Some(FieldValue::U64(3)),
+            Some(FieldValue::F64(12.5)),
+            Some(FieldValue::String("Main St".to_string())),
+            Some(FieldValue::F64(45.0)),
+            Some(FieldValue::F64(-122.0)),
+        ])
+        .unwrap();
+        assert_eq!(point, Point::new(7, -122.0, 45.0, 0.0, 12.5, Some(3), None));
+
+        // Points need coordinates and elevation, and a mistyped surface makes the whole point irrelevant
+        assert_eq!(
+            read([
+                None,
+                None,
+                None,
+                Some(FieldValue::F64(45.0)),
+                Some(FieldValue::F64(-122.0))
+            ]),
+            None
+        );
+        assert_eq!(
+            read([
+                Some(FieldValue::F64(3.0)),
+                Some(FieldValue::F64(12.5)),
+                None,
+                Some(FieldValue::F64(45.0)),
+                Some(FieldValue::F64(-122.0)),
+            ]),
+            None
+        );
+    }
 }
@@ -0,0 +1,125 @@
+use crate::error::rust::TracklibRwgpsError;
+use crate::geometry::Point;
+use crate::xml;
+use std::borrow::Borrow;
+use std::fmt::Write;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GpxType {
+    Route,
+    Track,
+}
+
+impl FromStr for GpxType {
+    type Err = TracklibRwgpsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rte" => Ok(GpxType::Route),
+            "trk" => Ok(GpxType::Track),
+            _ => Err(TracklibRwgpsError::InvalidExportOption(format!(
+                "GPX type '{s}' is not valid"
+            ))),
+        }
+    }
+}
+
+fn write_point(output: &mut String, tag: &str, point: &Point) {
+    write!(
+        output,
+        r#"<{tag} lat="{}" lon="{}"><ele>{}</ele>"#,
+        point.y(),
+        point.x(),
+        point.e()
+    )
+    .unwrap();
+    if let Some(t) = point.t() {
+        output.push_str("<time>");
+        xml::write_timestamp(output, t);
+        output.push_str("</time>");
+    }
+    write!(output, "</{tag}>").unwrap();
+}
+
+pub fn gpx_encode<I>(points: I, gpx_type: GpxType, name: Option<&str>) -> String
+where
+    I: IntoIterator,
+    I::Item: Borrow<Point>,
+{
+    let mut output = String::new();
+    output.push_str(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    output.push_str(r#"<gpx version="1.1" creator="tracklib_rwgps" xmlns="http://www.topografix.com/GPX/1/1">"#);
+
+    let (open, point_tag, close) = match gpx_type {
+        GpxType::Route => ("<rte>", "rtept", "</rte>"),
+        GpxType::Track => ("<trk>", "trkpt", "</trkseg></trk>"),
+    };
+
+    output.push_str(open);
+    if let Some(name) = name {
+        output.push_str("<name>");
+        xml::escape(&mut output, name);
+        output.push_str("</name>");
+    }
+    if gpx_type == GpxType::Track {
+        output.push_str("<trkseg>");
+    }
+    for point in points {
+        write_point(&mut output, point_tag, point.borrow());
+    }
+    output.push_str(close);
+
+    output.push_str("</gpx>");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gpx_types() {
+        let points = vec![
+            Point::new(0, -122.5, 45.25, 0.0, 10.0, None, None).with_time(Some(1_600_000_000), None),
+            Point::new(1, -122.0, 45.5, 0.0, 12.5, None, None),
+        ];
+        let header = concat!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+            r#"<gpx version="1.1" creator="tracklib_rwgps" xmlns="http://www.topografix.com/GPX/1/1">"#
+        );
+
+        assert_eq!(
+            gpx_encode(&points, GpxType::Track, Some("Morning & Ride")),
+            format!(
+                "{header}{}",
+                concat!(
+                    r#"<trk><name>Morning &amp; Ride</name><trkseg>"#,
+                    r#"<trkpt lat="45.25" lon="-122.5"><ele>10</ele><time>2020-09-13T12:26:40Z</time></trkpt>"#,
+                    r#"<trkpt lat="45.5" lon="-122"><ele>12.5</ele></trkpt>"#,
+                    r#"</trkseg></trk></gpx>"#
+                )
+            )
+        );
+
+        assert_eq!(
+            gpx_encode(&points, GpxType::Route, None),
+            format!(
+                "{header}{}",
+                concat!(
+                    r#"<rte>"#,
+                    r#"<rtept lat="45.25" lon="-122.5"><ele>10</ele><time>2020-09-13T12:26:40Z</time></rtept>"#,
+                    r#"<rtept lat="45.5" lon="-122"><ele>12.5</ele></rtept>"#,
+                    r#"</rte></gpx>"#
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test_gpx_type_from_str() {
+        assert_eq!("rte".parse::<GpxType>().unwrap(), GpxType::Route);
+        assert_eq!("trk".parse::<GpxType>().unwrap(), GpxType::Track);
+        assert!("wpt".parse::<GpxType>().is_err());
+    }
+}
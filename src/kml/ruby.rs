@@ -0,0 +1,92 @@
+use super::rust::kml_encode;
+use crate::error::ruby::raise;
+use crate::error::rust::TracklibRwgpsError;
+use crate::geometry::{section_to_points, IrrelevantPointsBehavior};
+use crate::ruby::key_material_from_ruby;
+use crate::schema::ruby::RubyPointSchema;
+use crate::schema::rust::PointSchema;
+use crate::simplify::ruby::optional_simplify_options_from_ruby;
+use crate::simplify::rust::simplify_points;
+use crate::surface::ruby::RubySurfaceMapping;
+use itertools::Itertools;
+use rutie::{methods, module, AnyObject, Boolean, Integer, Object, RString, VM};
+
+module!(TracklibRwgps);
+
+methods!(
+    TracklibRwgps,
+    _rtself,
+    fn kml_section_data_kml(
+        track_reader: ruby_tracklib::read::TrackReader,
+        index: Integer,
+        name: RString,
+        mapping: RubySurfaceMapping,
+        tolerance: AnyObject,
+        style_by_surface: Boolean,
+        key_material: AnyObject,
+        point_schema: RubyPointSchema) -> RString {
+        let ruby_track_reader = track_reader.map_err(VM::raise_ex).unwrap();
+
+        let ruby_index = index.map_err(VM::raise_ex).unwrap();
+        let rust_index = usize::try_from(ruby_index.to_u64())
+            .map_err(|_| raise(TracklibRwgpsError::SectionMissing))
+            .unwrap();
+
+        let ruby_name = name.ok();
+        let rust_name = ruby_name.as_ref().map(|name| name.to_str());
+
+        // Points are only simplified when given both a SurfaceMapping and a tolerance, styling only needs the mapping
+        let ruby_mapping = mapping.ok();
+        let rust_simplify_opts = optional_simplify_options_from_ruby(tolerance.ok());
+        if ruby_mapping.is_none() && rust_simplify_opts.is_some() {
+            raise(TracklibRwgpsError::InvalidSimplifyOption(
+                "Simplifying a KML needs a SurfaceMapping as well as a tolerance".to_string(),
+            ));
+        }
+        let rust_style_by_surface = style_by_surface.is_ok_and(|style| style.to_bool());
+
+        let ruby_key_material = key_material.ok().and_then(key_material_from_ruby);
+        let rust_key_material = ruby_key_material.as_ref().map(|key| key.to_bytes_unchecked());
+
+        let rust_point_schema = point_schema
+            .ok()
+            .map_or_else(PointSchema::new, |ruby_point_schema| ruby_point_schema.inner().clone());
+
+        ruby_track_reader.with_track_reader(|track_reader| {
+            let mut section = track_reader
+                .section(rust_index)
+                .ok_or(TracklibRwgpsError::SectionMissing)
+                .map_err(raise)
+                .unwrap();
+            let points = section_to_points(
+                &mut section,
+                rust_key_material,
+                &rust_point_schema,
+                None,
+                IrrelevantPointsBehavior::Ignore,
+            )
+            .map_err(raise)
+            .unwrap();
+
+            let style_mapping = if rust_style_by_surface {
+                ruby_mapping.as_ref().map(|ruby_mapping| ruby_mapping.inner())
+            } else {
+                None
+            };
+
+            let kml = match (&ruby_mapping, &rust_simplify_opts) {
+                (Some(ruby_mapping), Some(rust_simplify_opts)) => {
+                    let simplified_points = simplify_points(&points, ruby_mapping.inner(), rust_simplify_opts)
+                        .into_iter()
+                        .sorted()
+                        .map(|index| points[index].clone())
+                        .collect::<Vec<_>>();
+                    kml_encode(&simplified_points, rust_name, style_mapping)
+                }
+                _ => kml_encode(&points, rust_name, style_mapping),
+            };
+
+            RString::from(kml)
+        })
+    }
+);
@@ -0,0 +1,152 @@
+use crate::geometry::Point;
+use crate::simplify::rust::{surface_group_lines, SurfaceSmoothing};
+use crate::surface::rust::SurfaceMapping;
+use crate::xml;
+use std::fmt::Write;
+
+// KML colors are aabbggrr. Surface groups take these in the order they first appear along the track.
+const SURFACE_GROUP_COLORS: [&str; 6] = ["ffe16941", "ff3c8cd2", "ff50af4c", "ffb0279c", "ff00c1ff", "ff7a7a7a"];
+const UNKNOWN_SURFACE_COLOR: &str = "ffbdbdbd";
+
+fn write_style(output: &mut String, id: &str, color: &str) {
+    write!(
+        output,
+        r#"<Style id="{id}"><LineStyle><color>{color}</color><width>4</width></LineStyle></Style>"#
+    )
+    .unwrap();
+}
+
+fn write_placemark(output: &mut String, name: Option<&str>, style_id: Option<&str>, line: &[Point]) {
+    output.push_str("<Placemark>");
+    if let Some(name) = name {
+        output.push_str("<name>");
+        xml::escape(output, name);
+        output.push_str("</name>");
+    }
+    if let Some(style_id) = style_id {
+        write!(output, "<styleUrl>#{style_id}</styleUrl>").unwrap();
+    }
+    output.push_str("<LineString><tessellate>1</tessellate><coordinates>");
+    for (i, point) in line.iter().enumerate() {
+        if i > 0 {
+            output.push(' ');
+        }
+        write!(output, "{},{},{}", point.x(), point.y(), point.e()).unwrap();
+    }
+    output.push_str("</coordinates></LineString></Placemark>");
+}
+
+// Without a mapping the points are a single Placemark. With one there's a Placemark per surface group partition,
+// named after and styled by its surface group.
+pub fn kml_encode(points: &[Point], name: Option<&str>, mapping: Option<&SurfaceMapping>) -> String {
+    let mut output = String::new();
+    output.push_str(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    output.push_str(r#"<kml xmlns="http://www.opengis.net/kml/2.2"><Document>"#);
+
+    if let Some(name) = name {
+        output.push_str("<name>");
+        xml::escape(&mut output, name);
+        output.push_str("</name>");
+    }
+
+    match mapping {
+        None => write_placemark(&mut output, None, None, points),
+        Some(mapping) => {
+            let lines = surface_group_lines(points, mapping, SurfaceSmoothing::None);
+
+            let mut groups: Vec<Option<&String>> = Vec::new();
+            for (group, _) in lines.iter() {
+                if !groups.contains(group) {
+                    groups.push(*group);
+                }
+            }
+
+            let style_ids = groups
+                .iter()
+                .enumerate()
+                .map(|(i, group)| match group {
+                    Some(_) => format!("surface-{i}"),
+                    None => "surface-unknown".to_string(),
+                })
+                .collect::<Vec<_>>();
+
+            for (i, (group, style_id)) in groups.iter().zip(style_ids.iter()).enumerate() {
+                let color = match group {
+                    Some(_) => SURFACE_GROUP_COLORS[i % SURFACE_GROUP_COLORS.len()],
+                    None => UNKNOWN_SURFACE_COLOR,
+                };
+                write_style(&mut output, style_id, color);
+            }
+
+            for (group, line) in lines {
+                let style_id = groups
+                    .iter()
+                    .position(|existing_group| *existing_group == group)
+                    .map(|i| style_ids[i].as_str());
+                write_placemark(&mut output, group.map(|group| group.as_str()), style_id, line);
+            }
+        }
+    }
+
+    output.push_str("</Document></kml>");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_placemark() {
+        let points = vec![
+            Point::new(0, -122.5, 45.25, 0.0, 10.0, None, None),
+            Point::new(1, -122.0, 45.5, 0.0, 12.5, None, None),
+        ];
+
+        assert_eq!(
+            kml_encode(&points, Some("Loop"), None),
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                r#"<kml xmlns="http://www.opengis.net/kml/2.2"><Document><name>Loop</name>"#,
+                "<Placemark><LineString><tessellate>1</tessellate>",
+                "<coordinates>-122.5,45.25,10 -122,45.5,12.5</coordinates>",
+                "</LineString></Placemark></Document></kml>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_placemarks_styled_by_surface_group() {
+        let mut mapping = SurfaceMapping::new(99);
+        mapping.add_surface(1, "Paved".to_string());
+        mapping.add_surface(2, "Gravel".to_string());
+
+        let points = vec![
+            Point::new(0, 0.0, 0.0, 0.0, 1.0, Some(1), None),
+            Point::new(1, 1.0, 0.0, 0.0, 2.0, Some(2), None),
+            Point::new(2, 2.0, 0.0, 0.0, 3.0, None, None),
+            Point::new(3, 3.0, 0.0, 0.0, 4.0, Some(1), None),
+            Point::new(4, 4.0, 0.0, 0.0, 5.0, Some(1), None),
+        ];
+
+        assert_eq!(
+            kml_encode(&points, None, Some(&mapping)),
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                r#"<kml xmlns="http://www.opengis.net/kml/2.2"><Document>"#,
+                r#"<Style id="surface-0"><LineStyle><color>ffe16941</color><width>4</width></LineStyle></Style>"#,
+                r#"<Style id="surface-1"><LineStyle><color>ff3c8cd2</color><width>4</width></LineStyle></Style>"#,
+                r#"<Style id="surface-unknown"><LineStyle><color>ffbdbdbd</color><width>4</width></LineStyle></Style>"#,
+                "<Placemark><name>Paved</name><styleUrl>#surface-0</styleUrl><LineString><tessellate>1</tessellate>",
+                "<coordinates>0,0,1 1,0,2</coordinates></LineString></Placemark>",
+                "<Placemark><name>Gravel</name><styleUrl>#surface-1</styleUrl><LineString><tessellate>1</tessellate>",
+                "<coordinates>1,0,2 2,0,3</coordinates></LineString></Placemark>",
+                "<Placemark><styleUrl>#surface-unknown</styleUrl><LineString><tessellate>1</tessellate>",
+                "<coordinates>2,0,3 3,0,4</coordinates></LineString></Placemark>",
+                "<Placemark><name>Paved</name><styleUrl>#surface-0</styleUrl><LineString><tessellate>1</tessellate>",
+                "<coordinates>3,0,4 4,0,5</coordinates></LineString></Placemark>",
+                "</Document></kml>"
+            )
+        );
+    }
+}
@@ -1,10 +1,47 @@
+mod error;
+mod geojson;
 mod geometry;
+mod gpx;
+mod kml;
 mod polyline;
+mod region;
+#[cfg(feature = "ruby")]
+mod ruby;
+mod schema;
 mod simplify;
+mod stats;
 mod surface;
+mod tcx;
+mod track;
+mod xml;
 
+pub use error::rust::TracklibRwgpsError;
+#[cfg(feature = "serde")]
+pub use geojson::rust::geojson_encode;
+pub use geometry::{
+    reader_to_points, section_to_points, with_section_points, with_section_reader, IrrelevantPointsBehavior, Point,
+    PointReader,
+};
+pub use gpx::rust::{gpx_encode, GpxType};
+pub use kml::rust::kml_encode;
+pub use polyline::rust::{polyline_decode, polyline_encode, PointField, PolylineOption};
+pub use region::{Polygon, Region};
+pub use schema::rust::PointSchema;
+pub use simplify::rust::{
+    simplify_points, simplify_points_levels, simplify_points_significance, SimplifyAlgorithm, SimplifyElevation,
+    SimplifyOptions, SimplifyUnits, SurfaceSmoothing,
+};
+pub use stats::rust::{
+    elevation_stats, surface_segments, surface_summary, ElevationStats, SurfaceSegment, SurfaceSummary,
+};
+pub use surface::rust::{RoadClassId, RoadClassMapping, SurfaceMapping, SurfaceTypeId};
+pub use tcx::rust::tcx_encode;
+pub use track::rust::track_to_points;
+
+#[cfg(feature = "ruby")]
 use rutie::{Module, Object};
 
+#[cfg(feature = "ruby")]
 #[allow(non_snake_case)]
 #[no_mangle]
 pub extern "C" fn Init_Tracklib_Rwgps() {
@@ -13,23 +50,50 @@ pub extern "C" fn Init_Tracklib_Rwgps() {
             class.def_self("new", polyline::ruby::polyline_options_new);
         });
 
+        module.define_nested_class("PointSchema", None).define(|class| {
+            class.def_self("new", schema::ruby::point_schema_new);
+            class.def("to_s", schema::ruby::point_schema_to_s);
+        });
+
+        module.define_nested_class("SimplifyOptions", None).define(|class| {
+            class.def_self("new", simplify::ruby::simplify_options_new);
+            class.def("set_algorithm", simplify::ruby::simplify_options_set_algorithm);
+            class.def("set_units", simplify::ruby::simplify_options_set_units);
+            class.def("set_elevation", simplify::ruby::simplify_options_set_elevation);
+            class.def("set_max_points", simplify::ruby::simplify_options_set_max_points);
+            class.def(
+                "set_surface_smoothing",
+                simplify::ruby::simplify_options_set_surface_smoothing,
+            );
+            class.def(
+                "set_keep_road_class_changes",
+                simplify::ruby::simplify_options_set_keep_road_class_changes,
+            );
+            class.def("to_s", simplify::ruby::simplify_options_to_s);
+        });
+
         module.define_nested_class("RoadClassMapping", None).define(|class| {
             class.def_self("new", surface::ruby::road_class_mapping_new);
+            class.def_self("from_geojson", surface::ruby::road_class_mapping_from_geojson);
+            class.def_self("from_wkt", surface::ruby::road_class_mapping_from_wkt);
             class.def("add_road_class", surface::ruby::road_class_mapping_add_road_class);
             class.def("to_s", surface::ruby::road_class_mapping_to_s);
         });
 
         module.define_nested_class("SurfaceMapping", None).define(|class| {
             class.def_self("new", surface::ruby::surface_mapping_new);
+            class.def_self("from_json", surface::ruby::surface_mapping_from_json);
             class.def("add_surface", surface::ruby::surface_mapping_add_surface);
             class.def(
                 "add_road_class_mapping",
                 surface::ruby::surface_mapping_add_road_class_mapping,
             );
+            class.def("to_json", surface::ruby::surface_mapping_to_json);
             class.def("to_s", surface::ruby::surface_mapping_to_s);
         });
 
         module.define_module_function("section_data_polyline", polyline::ruby::polyline_section_data_polyline);
+        module.define_module_function("decode_polyline", polyline::ruby::polyline_decode_polyline);
         module.define_module_function(
             "section_data_simplified_polyline",
             simplify::ruby::simplify_section_data_simplified_polyline,
@@ -42,5 +106,26 @@ pub extern "C" fn Init_Tracklib_Rwgps() {
             "section_column_simplified",
             simplify::ruby::simplify_section_column_simplified,
         );
+        module.define_module_function(
+            "section_data_simplified_polyline_levels",
+            simplify::ruby::simplify_section_data_simplified_polyline_levels,
+        );
+        module.define_module_function(
+            "section_simplified_indexes_levels",
+            simplify::ruby::simplify_section_simplified_indexes_levels,
+        );
+        module.define_module_function(
+            "section_simplified_significance",
+            simplify::ruby::simplify_section_simplified_significance,
+        );
+        module.define_module_function("section_data_geojson", geojson::ruby::geojson_section_data_geojson);
+        module.define_module_function("section_data_gpx", gpx::ruby::gpx_section_data_gpx);
+        module.define_module_function("section_data_tcx", tcx::ruby::tcx_section_data_tcx);
+        module.define_module_function("section_data_kml", kml::ruby::kml_section_data_kml);
+        module.define_module_function("section_elevation_stats", stats::ruby::stats_section_elevation_stats);
+        module.define_module_function("section_surface_summary", stats::ruby::stats_section_surface_summary);
+        module.define_module_function("section_surface_segments", stats::ruby::stats_section_surface_segments);
+        module.define_module_function("track_data_polyline", track::ruby::track_track_data_polyline);
+        module.define_module_function("track_data_simplified", track::ruby::track_track_data_simplified);
     });
 }
@@ -1,7 +1,13 @@
-use super::rust::{polyline_encode, PointField, PolylineOption};
-use crate::geometry::{reader_to_points, IrrelevantPointsBehavior};
+use super::rust::{polyline_decode, polyline_encode, PointField, PolylineOption};
+use crate::error::ruby::raise;
+use crate::error::rust::TracklibRwgpsError;
+use crate::geometry::{with_section_points, IrrelevantPointsBehavior, Point};
+use crate::ruby::key_material_from_ruby;
+use crate::schema::ruby::RubyPointSchema;
+use crate::schema::rust::PointSchema;
+use itertools::process_results;
 use rutie::{
-    class, methods, module, wrappable_struct, AnyObject, Array, Class, Integer, Module, Object, RString,
+    class, methods, module, wrappable_struct, AnyObject, Array, Float, Hash, Integer, Module, Object, RString,
     VerifiedObject, VM,
 };
 
@@ -43,83 +49,20 @@ methods!(
                 let precision = ruby_precision.to_u32();
                 let factor = f64::from(10_u32.pow(precision));
 
-                let field = match ruby_field_name.to_str() {
-                    "y" => {
-                        // disallow a default value (third array element)
-                        if !ruby_default_value.is_nil() {
-                            VM::raise(
-                                Class::from_existing("Exception"),
-                                "Polyline parameter 'y' does not allow a default value",
-                            );
-                        }
-                        PointField::Y
-                    }
-                    "x" => {
-                        // disallow a default value (third array element)
-                        if !ruby_default_value.is_nil() {
-                            VM::raise(
-                                Class::from_existing("Exception"),
-                                "Polyline parameter 'x' does not allow a default value",
-                            );
-                        }
-                        PointField::X
-                    }
-                    "d" => {
-                        // disallow a default value (third array element)
-                        if !ruby_default_value.is_nil() {
-                            VM::raise(
-                                Class::from_existing("Exception"),
-                                "Polyline parameter 'd' does not allow a default value",
-                            );
-                        }
-                        PointField::D
-                    }
-                    "e" => {
-                        // disallow a default value (third array element)
-                        if !ruby_default_value.is_nil() {
-                            VM::raise(
-                                Class::from_existing("Exception"),
-                                "Polyline parameter 'e' does not allow a default value",
-                            );
-                        }
-                        PointField::E
-                    }
-                    "S" => {
-                        if ruby_default_value.is_nil() {
-                            VM::raise(
-                                Class::from_existing("Exception"),
-                                "Polyline parameter 'S' requires a default value",
-                            );
-                        }
-                        let default = ruby_default_value
+                let default = if ruby_default_value.is_nil() {
+                    None
+                } else {
+                    Some(
+                        ruby_default_value
                             .try_convert_to::<Integer>()
                             .map_err(VM::raise_ex)
                             .unwrap()
-                            .to_u64();
-                        PointField::S { default }
-                    }
-                    "R" => {
-                        if ruby_default_value.is_nil() {
-                            VM::raise(
-                                Class::from_existing("Exception"),
-                                "Polyline parameter 'R' requires a default value",
-                            );
-                        }
-                        let default = ruby_default_value
-                            .try_convert_to::<Integer>()
-                            .map_err(VM::raise_ex)
-                            .unwrap()
-                            .to_u64();
-                        PointField::R { default }
-                    }
-                    field_name => {
-                        VM::raise(
-                            Class::from_existing("Exception"),
-                            &format!("Polyline parameter '{field_name}' is not valid"),
-                        );
-                        unreachable!();
-                    }
+                            .to_u64(),
+                    )
                 };
+                let field = PointField::new(ruby_field_name.to_str(), default)
+                    .map_err(raise)
+                    .unwrap();
 
                 PolylineOption::new(field, factor)
             })
@@ -156,55 +99,82 @@ methods!(
         track_reader: ruby_tracklib::read::TrackReader,
         index: Integer,
         polyline_opts: PolylineOptions,
-        key_material: RString) -> RString {
+        key_material: AnyObject,
+        point_schema: RubyPointSchema) -> RString {
         let ruby_track_reader = track_reader.map_err(VM::raise_ex).unwrap();
 
         let ruby_index = index.map_err(VM::raise_ex).unwrap();
         let rust_index = usize::try_from(ruby_index.to_u64())
-            .map_err(|_| VM::raise(Class::from_existing("Exception"), "u64 != usize"))
+            .map_err(|_| raise(TracklibRwgpsError::SectionMissing))
             .unwrap();
 
         let ruby_polyline_opts = polyline_opts.map_err(VM::raise_ex).unwrap();
         let rust_polyline_opts = ruby_polyline_opts.inner();
 
+        let ruby_key_material = key_material.ok().and_then(key_material_from_ruby);
+        let rust_key_material = ruby_key_material.as_ref().map(|key| key.to_bytes_unchecked());
+
+        let rust_point_schema = point_schema
+            .ok()
+            .map_or_else(PointSchema::new, |ruby_point_schema| ruby_point_schema.inner().clone());
+
         ruby_track_reader.with_track_reader(|track_reader| {
-            track_reader
+            let mut section = track_reader
                 .section(rust_index)
-                .map(|section| {
-                    let schema = tracklib::schema::Schema::with_fields(vec![
-                        tracklib::schema::FieldDefinition::new("x", tracklib::schema::DataType::F64 { scale: 6 }),
-                        tracklib::schema::FieldDefinition::new("y", tracklib::schema::DataType::F64 { scale: 6 }),
-                        tracklib::schema::FieldDefinition::new("e", tracklib::schema::DataType::F64 { scale: 1 }),
-                        tracklib::schema::FieldDefinition::new("S", tracklib::schema::DataType::U64),
-                        tracklib::schema::FieldDefinition::new("R", tracklib::schema::DataType::U64),
-                    ]);
-
-                    match section {
-                        tracklib::read::section::Section::Standard(section) => {
-                            let section_reader = section
-                                .reader_for_schema(&schema)
-                                .map_err(|e| VM::raise(Class::from_existing("Exception"), &format!("{}", e)))
-                                .unwrap();
-                            let points = reader_to_points(section_reader, IrrelevantPointsBehavior::Ignore);
-
-                            RString::from(polyline_encode(&points, rust_polyline_opts))
-                        }
-                        tracklib::read::section::Section::Encrypted(mut section) => {
-                            let ruby_key_material = key_material.map_err(VM::raise_ex).unwrap();
-                            let rust_key_material = ruby_key_material.to_bytes_unchecked();
-
-                            let section_reader = section
-                                .reader_for_schema(rust_key_material, &schema)
-                                .map_err(|e| VM::raise(Class::from_existing("Exception"), &format!("{}", e)))
-                                .unwrap();
-                            let points = reader_to_points(section_reader, IrrelevantPointsBehavior::Ignore);
-
-                            RString::from(polyline_encode(&points, rust_polyline_opts))
-                        }
-                    }
-                })
-                .ok_or_else(|| VM::raise(Class::from_existing("Exception"), "Section does not exist"))
-                .unwrap()
+                .ok_or(TracklibRwgpsError::SectionMissing)
+                .map_err(raise)
+                .unwrap();
+            let polyline = with_section_points(
+                &mut section,
+                rust_key_material,
+                &rust_point_schema,
+                None,
+                IrrelevantPointsBehavior::Ignore,
+                |points| process_results(points, |points| polyline_encode(points, rust_polyline_opts)),
+            )
+            .map_err(raise)
+            .unwrap();
+
+            RString::from(polyline)
         })
+    },
+    fn polyline_decode_polyline(polyline: RString, polyline_opts: PolylineOptions) -> Array {
+        let ruby_polyline = polyline.map_err(VM::raise_ex).unwrap();
+
+        let ruby_polyline_opts = polyline_opts.map_err(VM::raise_ex).unwrap();
+        let rust_polyline_opts = ruby_polyline_opts.inner();
+
+        polyline_decode(ruby_polyline.to_str(), rust_polyline_opts)
+            .map_err(raise)
+            .unwrap()
+            .iter()
+            .map(|point| point_to_hash(point, rust_polyline_opts))
+            .collect()
     }
 );
+
+fn point_to_hash(point: &Point, fields: &[PolylineOption]) -> Hash {
+    let mut hash = Hash::new();
+    for field in fields {
+        match field.field() {
+            PointField::Y => hash.store(RString::from("y"), Float::new(point.y())),
+            PointField::X => hash.store(RString::from("x"), Float::new(point.x())),
+            PointField::D => hash.store(RString::from("d"), Float::new(point.d())),
+            PointField::E => hash.store(RString::from("e"), Float::new(point.e())),
+            PointField::T => match point.t() {
+                Some(t) => hash.store(RString::from("t"), Integer::from(t)),
+                None => continue,
+            },
+            PointField::S { .. } => match point.s() {
+                Some(s) => hash.store(RString::from("S"), Integer::from(s)),
+                None => continue,
+            },
+            PointField::R { .. } => match point.r() {
+                Some(r) => hash.store(RString::from("R"), Integer::from(r)),
+                None => continue,
+            },
+        };
+    }
+
+    hash
+}
@@ -1,32 +1,58 @@
+use crate::error::rust::TracklibRwgpsError;
 use crate::geometry::Point;
 use crate::surface::rust::{RoadClassId, SurfaceTypeId};
+use std::borrow::Borrow;
 
 #[derive(Clone, Copy, Debug)]
-pub(crate) enum PointField {
+pub enum PointField {
     Y,
     X,
     D,
     E,
+    T,
     S { default: SurfaceTypeId },
     R { default: RoadClassId },
 }
 
+impl PointField {
+    pub fn new(field_name: &str, default: Option<u64>) -> Result<Self, TracklibRwgpsError> {
+        match (field_name, default) {
+            ("y", None) => Ok(PointField::Y),
+            ("x", None) => Ok(PointField::X),
+            ("d", None) => Ok(PointField::D),
+            ("e", None) => Ok(PointField::E),
+            ("t", None) => Ok(PointField::T),
+            ("S", Some(default)) => Ok(PointField::S { default }),
+            ("R", Some(default)) => Ok(PointField::R { default }),
+            ("y" | "x" | "d" | "e" | "t", Some(_)) => Err(TracklibRwgpsError::InvalidPolylineOption(format!(
+                "Polyline parameter '{field_name}' does not allow a default value"
+            ))),
+            ("S" | "R", None) => Err(TracklibRwgpsError::InvalidPolylineOption(format!(
+                "Polyline parameter '{field_name}' requires a default value"
+            ))),
+            _ => Err(TracklibRwgpsError::InvalidPolylineOption(format!(
+                "Polyline parameter '{field_name}' is not valid"
+            ))),
+        }
+    }
+}
+
 #[derive(Debug)]
-pub(crate) struct PolylineOption {
+pub struct PolylineOption {
     field: PointField,
     factor: f64,
 }
 
 impl PolylineOption {
-    pub(crate) fn new(field: PointField, factor: f64) -> Self {
+    pub fn new(field: PointField, factor: f64) -> Self {
         Self { field, factor }
     }
 
-    pub(crate) fn field(&self) -> PointField {
+    pub fn field(&self) -> PointField {
         self.field
     }
 
-    pub(crate) fn factor(&self) -> f64 {
+    pub fn factor(&self) -> f64 {
         self.factor
     }
 }
@@ -55,17 +81,28 @@ fn encode(current: f64, previous: f64, factor: f64) -> String {
     output
 }
 
-pub(crate) fn polyline_encode(points: &[Point], fields: &[PolylineOption]) -> String {
+pub fn polyline_encode<I>(points: I, fields: &[PolylineOption]) -> String
+where
+    I: IntoIterator,
+    I::Item: Borrow<Point>,
+{
     let mut output = String::new();
-    let mut prev = &Point::default();
+    let mut prev = Point::default();
 
     for point in points {
+        let point = point.borrow();
         for field in fields {
             match field.field() {
                 PointField::Y => output.push_str(&encode(point.y(), prev.y(), field.factor())),
                 PointField::X => output.push_str(&encode(point.x(), prev.x(), field.factor())),
                 PointField::D => output.push_str(&encode(point.d(), prev.d(), field.factor())),
                 PointField::E => output.push_str(&encode(point.e(), prev.e(), field.factor())),
+                // Points without a time are encoded as 0
+                PointField::T => output.push_str(&encode(
+                    point.t().unwrap_or(0) as f64,
+                    prev.t().unwrap_or(0) as f64,
+                    field.factor(),
+                )),
                 PointField::S {
                     default: default_surface_id,
                 } => output.push_str(&encode(
@@ -83,8 +120,227 @@ pub(crate) fn polyline_encode(points: &[Point], fields: &[PolylineOption]) -> St
             }
         }
 
-        prev = point;
+        prev = point.clone();
     }
 
     output
 }
+
+fn decode<I>(bytes: &mut I, previous: i64) -> Result<i64, TracklibRwgpsError>
+where
+    I: Iterator<Item = u8>,
+{
+    let mut v: i64 = 0;
+    let mut shift = 0;
+    loop {
+        let byte = bytes
+            .next()
+            .ok_or_else(|| TracklibRwgpsError::InvalidPolyline("unexpected end of input".to_string()))?;
+        let chunk = i64::from(byte) - 63;
+        if !(0..0x40).contains(&chunk) || shift >= 64 {
+            return Err(TracklibRwgpsError::InvalidPolyline(format!(
+                "invalid character '{}'",
+                char::from(byte)
+            )));
+        }
+        v |= (chunk & 0x1f) << shift;
+        shift += 5;
+        if chunk < 0x20 {
+            break;
+        }
+    }
+
+    let diff = if v & 1 != 0 { !(v >> 1) } else { v >> 1 };
+    previous
+        .checked_add(diff)
+        .ok_or_else(|| TracklibRwgpsError::InvalidPolyline("value out of range".to_string()))
+}
+
+fn unscale(n: i64, factor: f64) -> f64 {
+    n as f64 / factor
+}
+
+// Missing ids are encoded as the field's default, so an id that equals the default
+// can't be told apart from a missing one and decodes as None.
+fn unscale_id(n: i64, factor: f64, default: u64) -> Option<u64> {
+    let id = unscale(n, factor).round() as u64;
+    if id == default {
+        None
+    } else {
+        Some(id)
+    }
+}
+
+// Missing times are encoded as 0, so a time of exactly 0 decodes as None. Any other
+// time, including one before 1970, decodes to the value that was encoded.
+fn unscale_time(n: i64, factor: f64) -> Option<i64> {
+    let t = unscale(n, factor).round() as i64;
+    if t == 0 {
+        None
+    } else {
+        Some(t)
+    }
+}
+
+pub fn polyline_decode(polyline: &str, fields: &[PolylineOption]) -> Result<Vec<Point>, TracklibRwgpsError> {
+    if fields.is_empty() {
+        return if polyline.is_empty() {
+            Ok(Vec::new())
+        } else {
+            Err(TracklibRwgpsError::InvalidPolyline(
+                "cannot decode a polyline without any fields".to_string(),
+            ))
+        };
+    }
+
+    let mut bytes = polyline.bytes().peekable();
+    let mut previous = vec![0; fields.len()];
+    let mut points = Vec::new();
+
+    while bytes.peek().is_some() {
+        let (mut x, mut y, mut d, mut e, mut s, mut r, mut t) = (0.0, 0.0, 0.0, 0.0, None, None, None);
+
+        for (field, previous) in fields.iter().zip(previous.iter_mut()) {
+            *previous = decode(&mut bytes, *previous)?;
+
+            match field.field() {
+                PointField::Y => y = unscale(*previous, field.factor()),
+                PointField::X => x = unscale(*previous, field.factor()),
+                PointField::D => d = unscale(*previous, field.factor()),
+                PointField::E => e = unscale(*previous, field.factor()),
+                PointField::T => t = unscale_time(*previous, field.factor()),
+                PointField::S {
+                    default: default_surface_id,
+                } => s = unscale_id(*previous, field.factor(), default_surface_id),
+                PointField::R {
+                    default: default_road_class_id,
+                } => r = unscale_id(*previous, field.factor(), default_road_class_id),
+            }
+        }
+
+        points.push(Point::new(points.len(), x, y, d, e, s, r).with_time(t, None));
+    }
+
+    Ok(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_point_field_defaults() {
+        assert!(matches!(PointField::new("y", None), Ok(PointField::Y)));
+        assert!(matches!(
+            PointField::new("S", Some(99)),
+            Ok(PointField::S { default: 99 })
+        ));
+        assert!(matches!(PointField::new("t", None), Ok(PointField::T)));
+        assert!(matches!(
+            PointField::new("x", Some(99)),
+            Err(TracklibRwgpsError::InvalidPolylineOption(_))
+        ));
+        assert!(matches!(
+            PointField::new("R", None),
+            Err(TracklibRwgpsError::InvalidPolylineOption(_))
+        ));
+        assert!(matches!(
+            PointField::new("z", None),
+            Err(TracklibRwgpsError::InvalidPolylineOption(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_round_trip() {
+        let fields = vec![
+            PolylineOption::new(PointField::Y, 1e5),
+            PolylineOption::new(PointField::X, 1e5),
+            PolylineOption::new(PointField::E, 1e1),
+            PolylineOption::new(PointField::S { default: 99 }, 1e0),
+            PolylineOption::new(PointField::R { default: 0 }, 1e0),
+            PolylineOption::new(PointField::T, 1e0),
+        ];
+        let points = vec![
+            Point::new(0, -122.66972, 45.46463, 0.0, 35.7, Some(1), Some(4)).with_time(Some(1_600_000_000), None),
+            Point::new(1, -122.66984, 45.46541, 0.0, 42.1, None, Some(5)).with_time(Some(1_600_000_005), None),
+            Point::new(2, -122.66990, 45.46554, 0.0, 42.4, Some(20), None),
+        ];
+
+        let polyline = polyline_encode(&points, &fields);
+        assert_eq!(polyline_decode(&polyline, &fields).unwrap(), points);
+    }
+
+    #[test]
+    fn test_decode_round_trip_edge_values() {
+        let fields = vec![
+            PolylineOption::new(PointField::Y, 1e5),
+            PolylineOption::new(PointField::X, 1e5),
+            PolylineOption::new(PointField::S { default: 99 }, 1e0),
+            PolylineOption::new(PointField::R { default: 0 }, 1e0),
+            PolylineOption::new(PointField::T, 1e0),
+        ];
+        let points = vec![
+            Point::new(0, -122.66972, 45.46463, 0.0, 0.0, Some(99), Some(0)).with_time(Some(-86_400), None),
+            Point::new(1, -122.66984, 45.46541, 0.0, 0.0, Some(3), Some(2)).with_time(Some(0), None),
+            Point::new(2, -122.66990, 45.46554, 0.0, 0.0, None, None).with_time(Some(-1), None),
+        ];
+        let expected = vec![
+            Point::new(0, -122.66972, 45.46463, 0.0, 0.0, None, None).with_time(Some(-86_400), None),
+            Point::new(1, -122.66984, 45.46541, 0.0, 0.0, Some(3), Some(2)),
+            Point::new(2, -122.66990, 45.46554, 0.0, 0.0, None, None).with_time(Some(-1), None),
+        ];
+
+        let polyline = polyline_encode(&points, &fields);
+        assert_eq!(polyline_decode(&polyline, &fields).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_decode_field_order() {
+        let yx = vec![
+            PolylineOption::new(PointField::Y, 1e5),
+            PolylineOption::new(PointField::X, 1e5),
+        ];
+        let xy = vec![
+            PolylineOption::new(PointField::X, 1e5),
+            PolylineOption::new(PointField::Y, 1e5),
+        ];
+        let points = vec![
+            Point::new(0, 40.0, 12.0, 0.0, 0.0, None, None),
+            Point::new(1, 41.0, 800.0, 0.0, 0.0, None, None),
+        ];
+
+        assert_eq!(polyline_decode(&polyline_encode(&points, &yx), &yx).unwrap(), points);
+        assert_eq!(polyline_decode(&polyline_encode(&points, &xy), &xy).unwrap(), points);
+        assert_ne!(polyline_decode(&polyline_encode(&points, &yx), &xy).unwrap(), points);
+    }
+
+    #[test]
+    fn test_decode_invalid_polyline() {
+        let fields = vec![
+            PolylineOption::new(PointField::Y, 1e5),
+            PolylineOption::new(PointField::X, 1e5),
+        ];
+
+        assert_eq!(polyline_decode("", &fields).unwrap(), vec![]);
+        assert!(matches!(
+            polyline_decode("_p~iF", &fields),
+            Err(TracklibRwgpsError::InvalidPolyline(_))
+        ));
+        assert!(matches!(
+            polyline_decode("_p~iF ", &fields),
+            Err(TracklibRwgpsError::InvalidPolyline(_))
+        ));
+    }
+    #[test]
+    fn test_decode_overflowing_polyline() {
+        let fields = vec![PolylineOption::new(PointField::E, 1e0)];
+
+        // Each value is a diff of 2^62 - 1, so the third one overflows an i64
+        let value = format!("}}{}F", "~".repeat(11));
+        assert_eq!(polyline_decode(&value.repeat(2), &fields).unwrap().len(), 2);
+        assert!(matches!(
+            polyline_decode(&value.repeat(3), &fields),
+            Err(TracklibRwgpsError::InvalidPolyline(_))
+        ));
+    }
+}
@@ -0,0 +1,365 @@
+use crate::error::rust::TracklibRwgpsError;
+#[cfg(feature = "serde")]
+use serde_json::Value;
+
+// A polygon as lon/lat rings, the outer boundary first and holes after it. Rings don't need to repeat their first
+// position at the end.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polygon {
+    rings: Vec<Vec<[f64; 2]>>,
+}
+
+impl Polygon {
+    pub fn new(rings: Vec<Vec<[f64; 2]>>) -> Result<Self, TracklibRwgpsError> {
+        if rings.is_empty() {
+            return Err(TracklibRwgpsError::InvalidRegion(
+                "Polygon must have at least one ring".to_string(),
+            ));
+        }
+        if rings.iter().any(|ring| ring.len() < 3) {
+            return Err(TracklibRwgpsError::InvalidRegion(
+                "Polygon rings must have at least 3 positions".to_string(),
+            ));
+        }
+        if rings.iter().flatten().flatten().any(|v| !v.is_finite()) {
+            return Err(TracklibRwgpsError::InvalidRegion(
+                "Polygon coordinates must be finite".to_string(),
+            ));
+        }
+
+        Ok(Self { rings })
+    }
+
+    pub fn rings(&self) -> &[Vec<[f64; 2]>] {
+        &self.rings
+    }
+
+    // Even-odd rule, so holes fall out of the containment test without special casing
+    fn contains(&self, x: f64, y: f64) -> bool {
+        let mut inside = false;
+        for ring in &self.rings {
+            let mut j = ring.len() - 1;
+            for i in 0..ring.len() {
+                let [xi, yi] = ring[i];
+                let [xj, yj] = ring[j];
+                if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+                    inside = !inside;
+                }
+                j = i;
+            }
+        }
+        inside
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Region {
+    // [min_lat, min_lon, max_lat, max_lon], min_lon > max_lon for bboxes crossing the antimeridian
+    Bbox([f64; 4]),
+    Polygons(Vec<Polygon>),
+}
+
+impl Region {
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        match self {
+            // A bbox with min_lon > max_lon crosses the antimeridian and wraps around
+            Region::Bbox(bbox) if bbox[1] > bbox[3] => bbox[0] < y && bbox[2] > y && (bbox[1] < x || bbox[3] > x),
+            Region::Bbox(bbox) => bbox[0] < y && bbox[1] < x && bbox[2] > y && bbox[3] > x,
+            Region::Polygons(polygons) => polygons.iter().any(|polygon| polygon.contains(x, y)),
+        }
+    }
+
+    // Bounding box of the region, in the same [min_lat, min_lon, max_lat, max_lon] order as Region::Bbox
+    pub fn bbox(&self) -> [f64; 4] {
+        match self {
+            Region::Bbox(bbox) => *bbox,
+            Region::Polygons(polygons) => polygons.iter().flat_map(|polygon| polygon.rings.iter().flatten()).fold(
+                [f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY],
+                |bbox, [x, y]| [bbox[0].min(*y), bbox[1].min(*x), bbox[2].max(*y), bbox[3].max(*x)],
+            ),
+        }
+    }
+
+    // Accepts a Polygon or MultiPolygon geometry, or a Feature or FeatureCollection made of them
+    #[cfg(feature = "serde")]
+    pub fn from_geojson(geojson: &str) -> Result<Self, TracklibRwgpsError> {
+        let value = serde_json::from_str::<Value>(geojson)
+            .map_err(|e| TracklibRwgpsError::InvalidRegion(format!("Invalid JSON: {e}")))?;
+        let mut polygons = Vec::new();
+        geojson_polygons(&value, &mut polygons)?;
+        Ok(Region::Polygons(polygons))
+    }
+
+    // Accepts a POLYGON or MULTIPOLYGON
+    pub fn from_wkt(wkt: &str) -> Result<Self, TracklibRwgpsError> {
+        let invalid =
+            || TracklibRwgpsError::InvalidRegion("Invalid WKT, expected a POLYGON or MULTIPOLYGON".to_string());
+
+        let wkt = wkt.trim();
+        let split = wkt.find('(').ok_or_else(invalid)?;
+        let (tag, body) = wkt.split_at(split);
+        let rings_depth = match tag.trim().to_ascii_uppercase().as_str() {
+            "POLYGON" => 1,
+            "MULTIPOLYGON" => 2,
+            _ => return Err(invalid()),
+        };
+
+        let mut tokens = WktTokens { input: body, pos: 0 };
+        let nested = wkt_list(&mut tokens, rings_depth + 1).ok_or_else(invalid)?;
+        if tokens.next().is_some() {
+            return Err(invalid());
+        }
+
+        let polygons = match nested {
+            WktList::Lists(lists) if rings_depth == 1 => vec![wkt_polygon(WktList::Lists(lists))?],
+            WktList::Lists(lists) => lists.into_iter().map(wkt_polygon).collect::<Result<_, _>>()?,
+            WktList::Positions(_) => return Err(invalid()),
+        };
+        Ok(Region::Polygons(polygons))
+    }
+}
+
+#[cfg(feature = "serde")]
+fn geojson_error(message: &str) -> TracklibRwgpsError {
+    TracklibRwgpsError::InvalidRegion(format!("Invalid GeoJSON: {message}"))
+}
+
+#[cfg(feature = "serde")]
+fn geojson_polygons(value: &Value, polygons: &mut Vec<Polygon>) -> Result<(), TracklibRwgpsError> {
+    let coordinates = || {
+        value
+            .get("coordinates")
+            .ok_or_else(|| geojson_error("missing coordinates"))
+    };
+
+    match value.get("type").and_then(Value::as_str) {
+        Some("FeatureCollection") => {
+            let features = value
+                .get("features")
+                .and_then(Value::as_array)
+                .ok_or_else(|| geojson_error("missing features"))?;
+            for feature in features {
+                geojson_polygons(feature, polygons)?;
+            }
+        }
+        Some("Feature") => {
+            let geometry = value.get("geometry").ok_or_else(|| geojson_error("missing geometry"))?;
+            geojson_polygons(geometry, polygons)?;
+        }
+        Some("Polygon") => polygons.push(geojson_polygon(coordinates()?)?),
+        Some("MultiPolygon") => polygons.extend(geojson_multi_polygon(coordinates()?)?),
+        Some(other) => return Err(geojson_error(&format!("unsupported type {other}"))),
+        None => return Err(geojson_error("missing type")),
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+fn geojson_array(value: &Value) -> Result<&[Value], TracklibRwgpsError> {
+    value
+        .as_array()
+        .map(Vec::as_slice)
+        .ok_or_else(|| geojson_error("coordinates must be arrays"))
+}
+
+#[cfg(feature = "serde")]
+fn geojson_polygon(coordinates: &Value) -> Result<Polygon, TracklibRwgpsError> {
+    let rings = geojson_array(coordinates)?
+        .iter()
+        .map(|ring| {
+            geojson_array(ring)?
+                .iter()
+                .map(|position| match geojson_array(position)? {
+                    [x, y, ..] => match (x.as_f64(), y.as_f64()) {
+                        (Some(x), Some(y)) => Ok([x, y]),
+                        _ => Err(geojson_error("positions must be numbers")),
+                    },
+                    _ => Err(geojson_error("positions must have at least 2 values")),
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Polygon::new(rings)
+}
+
+#[cfg(feature = "serde")]
+fn geojson_multi_polygon(coordinates: &Value) -> Result<Vec<Polygon>, TracklibRwgpsError> {
+    geojson_array(coordinates)?.iter().map(geojson_polygon).collect()
+}
+
+struct WktTokens<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Iterator for WktTokens<'a> {
+    type Item = &'a str;
+
+    // Yields "(", ")", "," or a single position such as "1.5 -2"
+    fn next(&mut self) -> Option<&'a str> {
+        let rest = &self.input[self.pos..];
+        let trimmed = rest.trim_start();
+        self.pos += rest.len() - trimmed.len();
+
+        let len = match trimmed.chars().next()? {
+            '(' | ')' | ',' => 1,
+            _ => trimmed.find(['(', ')', ',']).unwrap_or(trimmed.len()),
+        };
+        self.pos += len;
+        Some(trimmed[..len].trim_end())
+    }
+}
+
+enum WktList {
+    Positions(Vec<[f64; 2]>),
+    Lists(Vec<WktList>),
+}
+
+// Reads a parenthesised list nested `depth` levels deep, where the innermost level holds positions
+fn wkt_list(tokens: &mut WktTokens, depth: usize) -> Option<WktList> {
+    if tokens.next()? != "(" {
+        return None;
+    }
+
+    let mut positions = Vec::new();
+    let mut lists = Vec::new();
+    loop {
+        if depth == 1 {
+            let mut values = tokens.next()?.split_whitespace().map(|v| v.parse::<f64>().ok());
+            positions.push([values.next()??, values.next()??]);
+        } else {
+            lists.push(wkt_list(tokens, depth - 1)?);
+        }
+
+        match tokens.next()? {
+            "," => continue,
+            ")" => break,
+            _ => return None,
+        }
+    }
+
+    Some(if depth == 1 {
+        WktList::Positions(positions)
+    } else {
+        WktList::Lists(lists)
+    })
+}
+
+fn wkt_polygon(list: WktList) -> Result<Polygon, TracklibRwgpsError> {
+    match list {
+        WktList::Lists(rings) => Polygon::new(
+            rings
+                .into_iter()
+                .filter_map(|ring| match ring {
+                    WktList::Positions(positions) => Some(positions),
+                    WktList::Lists(_) => None,
+                })
+                .collect(),
+        ),
+        WktList::Positions(_) => Err(TracklibRwgpsError::InvalidRegion("Invalid WKT polygon".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square_with_hole() -> Polygon {
+        Polygon::new(vec![
+            vec![[0.0, 0.0], [10.0, 0.0], [10.0, 10.0], [0.0, 10.0], [0.0, 0.0]],
+            vec![[4.0, 4.0], [6.0, 4.0], [6.0, 6.0], [4.0, 6.0]],
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_polygon_contains() {
+        let region = Region::Polygons(vec![square_with_hole()]);
+
+        assert!(region.contains(1.0, 1.0));
+        assert!(region.contains(9.0, 5.0));
+        assert!(!region.contains(5.0, 5.0)); // In the hole
+        assert!(!region.contains(11.0, 5.0));
+        assert!(!region.contains(-1.0, -1.0));
+        assert_eq!(region.bbox(), [0.0, 0.0, 10.0, 10.0]);
+    }
+
+    #[test]
+    fn test_bbox_contains() {
+        let region = Region::Bbox([-10.0, -20.0, 10.0, 20.0]);
+        assert!(region.contains(0.0, 0.0));
+        assert!(!region.contains(30.0, 0.0));
+        assert!(!region.contains(0.0, 30.0));
+
+        // Crossing the antimeridian
+        let region = Region::Bbox([-20.0, 170.0, -10.0, -170.0]);
+        assert!(region.contains(178.0, -15.0));
+        assert!(region.contains(-178.0, -15.0));
+        assert!(!region.contains(0.0, -15.0));
+        assert!(!region.contains(178.0, 0.0));
+    }
+
+    #[test]
+    fn test_invalid_polygons() {
+        assert!(Polygon::new(vec![]).is_err());
+        assert!(Polygon::new(vec![vec![[0.0, 0.0], [1.0, 1.0]]]).is_err());
+        assert!(Polygon::new(vec![vec![[0.0, 0.0], [1.0, 1.0], [f64::NAN, 0.0]]]).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_from_geojson() {
+        let polygon = r#"{"type": "Polygon", "coordinates": [
+            [[0, 0], [10, 0], [10, 10], [0, 10], [0, 0]],
+            [[4, 4], [6, 4], [6, 6], [4, 6], [4, 4]]
+        ]}"#;
+        assert_eq!(
+            Region::from_geojson(polygon).unwrap(),
+            Region::Polygons(vec![Polygon::new(vec![
+                vec![[0.0, 0.0], [10.0, 0.0], [10.0, 10.0], [0.0, 10.0], [0.0, 0.0]],
+                vec![[4.0, 4.0], [6.0, 4.0], [6.0, 6.0], [4.0, 6.0], [4.0, 4.0]],
+            ])
+            .unwrap()])
+        );
+
+        let collection = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"name": "a"}, "geometry":
+                {"type": "MultiPolygon", "coordinates": [[[[0, 0], [1, 0], [1, 1]]], [[[5, 5], [6, 5], [6, 6, 100]]]]}},
+            {"type": "Feature", "properties": null, "geometry":
+                {"type": "Polygon", "coordinates": [[[20, 20], [21, 20], [21, 21]]]}}
+        ]}"#;
+        match Region::from_geojson(collection).unwrap() {
+            Region::Polygons(polygons) => assert_eq!(polygons.len(), 3),
+            region => panic!("Unexpected region {region:?}"),
+        }
+
+        assert!(Region::from_geojson("{").is_err());
+        assert!(Region::from_geojson(&"[".repeat(100_000)).is_err());
+        assert!(Region::from_geojson(r#"{"type": "Polygon\ud800\u0041", "coordinates": []}"#).is_err());
+        assert!(Region::from_geojson(r#"{"type": "Point", "coordinates": [0, 0]}"#).is_err());
+        assert!(Region::from_geojson(r#"{"type": "Polygon", "coordinates": [[[0, 0], [1, "a"], [1, 1]]]}"#).is_err());
+        assert!(Region::from_geojson(r#"{"type": "Polygon"}"#).is_err());
+    }
+
+    #[test]
+    fn test_from_wkt() {
+        assert_eq!(
+            Region::from_wkt("POLYGON ((0 0, 10 0, 10 10, 0 10, 0 0), (4 4, 6 4, 6 6, 4 6))").unwrap(),
+            Region::Polygons(vec![square_with_hole()])
+        );
+
+        match Region::from_wkt("multipolygon(((0 0,1 0,1 1)),((5 5,6 5,6 6),(5.5 5.2,5.8 5.2,5.8 5.5)))").unwrap() {
+            Region::Polygons(polygons) => assert_eq!(polygons.len(), 2),
+            region => panic!("Unexpected region {region:?}"),
+        }
+
+        assert!(Region::from_wkt("POINT (0 0)").is_err());
+        assert!(Region::from_wkt("POLYGON EMPTY").is_err());
+        assert!(Region::from_wkt("POLYGON ((0 0, 1 0, 1 1)").is_err());
+        assert!(Region::from_wkt("POLYGON ((0 0, 1 0, 1 1)) x").is_err());
+        assert!(Region::from_wkt("POLYGON ((0 0, 1 0, 1 a))").is_err());
+        assert!(Region::from_wkt("POLYGON (0 0, 1 0, 1 1)").is_err());
+    }
+}
@@ -0,0 +1,23 @@
+use rutie::{AnyObject, Float, Integer, Object, RString, VM};
+
+// Key material is a String, or nil for a section with Standard encoding. Anything else raises a TypeError rather than
+// quietly reading the section without a key.
+pub(crate) fn key_material_from_ruby(key_material: AnyObject) -> Option<RString> {
+    if key_material.is_nil() {
+        None
+    } else {
+        Some(key_material.try_convert_to::<RString>().map_err(VM::raise_ex).unwrap())
+    }
+}
+
+// Numbers such as distances and counts can be given as an Integer as well as a Float, anything else raises a TypeError
+pub(crate) fn f64_from_ruby(number: AnyObject) -> f64 {
+    match number.try_convert_to::<Float>() {
+        Ok(f) => f.to_f64(),
+        Err(float_e) => number
+            .try_convert_to::<Integer>()
+            .map_err(|_| VM::raise_ex(float_e))
+            .unwrap()
+            .to_i64() as f64,
+    }
+}
@@ -0,0 +1,87 @@
+use super::rust::PointSchema;
+use crate::error::ruby::raise;
+use crate::error::rust::TracklibRwgpsError;
+use rutie::{class, methods, wrappable_struct, AnyObject, Array, Integer, Module, Object, RString, VerifiedObject, VM};
+
+pub struct WrappablePointSchema {
+    point_schema: PointSchema,
+}
+
+wrappable_struct!(WrappablePointSchema, PointSchemaWrapper, POINT_SCHEMA_WRAPPER_INSTANCE);
+
+class!(RubyPointSchema);
+
+methods!(
+    RubyPointSchema,
+    rtself,
+    fn point_schema_new(ruby_columns: Array) -> AnyObject {
+        // Every column is optional, those that aren't given keep their default name and scale
+        let mut columns = Vec::new();
+        if let Ok(ruby_columns) = ruby_columns {
+            for ele in ruby_columns.into_iter() {
+                let ruby_column = ele.try_convert_to::<Array>().map_err(VM::raise_ex).unwrap();
+
+                let ruby_field_name = ruby_column
+                    .at(0)
+                    .try_convert_to::<RString>()
+                    .map_err(VM::raise_ex)
+                    .unwrap();
+                let ruby_column_name = ruby_column
+                    .at(1)
+                    .try_convert_to::<RString>()
+                    .map_err(VM::raise_ex)
+                    .unwrap();
+                let ruby_scale = ruby_column.at(2);
+
+                let scale = if ruby_scale.is_nil() {
+                    None
+                } else {
+                    Some(
+                        u8::try_from(ruby_scale.try_convert_to::<Integer>().map_err(VM::raise_ex).unwrap().to_u64())
+                            .map_err(|_| {
+                                raise(TracklibRwgpsError::InvalidPointSchema(
+                                    "Point schema scale is too large".to_string(),
+                                ))
+                            })
+                            .unwrap(),
+                    )
+                };
+
+                columns.push((ruby_field_name.to_string(), ruby_column_name.to_string(), scale));
+            }
+        }
+
+        let point_schema = PointSchema::with_columns(
+            columns
+                .iter()
+                .map(|(field, name, scale)| (field.as_str(), name.as_str(), *scale)),
+        )
+        .map_err(raise)
+        .unwrap();
+
+        Module::from_existing("TracklibRwgps")
+            .get_nested_class("PointSchema")
+            .wrap_data(WrappablePointSchema { point_schema }, &*POINT_SCHEMA_WRAPPER_INSTANCE)
+    },
+    fn point_schema_to_s() -> RString {
+        let point_schema = &rtself.get_data(&*POINT_SCHEMA_WRAPPER_INSTANCE).point_schema;
+
+        RString::new_utf8(&format!("{:?}", point_schema))
+    }
+);
+
+impl RubyPointSchema {
+    pub(crate) fn inner(&self) -> &PointSchema {
+        &self.get_data(&*POINT_SCHEMA_WRAPPER_INSTANCE).point_schema
+    }
+}
+
+impl VerifiedObject for RubyPointSchema {
+    fn is_correct_type<T: Object>(object: &T) -> bool {
+        object.class() == Module::from_existing("TracklibRwgps").get_nested_class("PointSchema")
+    }
+
+    fn error_message() -> &'static str {
+        "Error converting to PointSchema"
+    }
+}
@@ -0,0 +1,204 @@
+use crate::error::rust::TracklibRwgpsError;
+use std::collections::HashSet;
+use tracklib::schema::{DataType, FieldDefinition, Schema};
+
+#[derive(Clone, Debug)]
+pub struct PointSchema {
+    x: String,
+    x_scale: u8,
+    y: String,
+    y_scale: u8,
+    e: String,
+    e_scale: u8,
+    s: String,
+    r: String,
+    t: Option<String>,
+}
+
+impl PointSchema {
+    pub fn new() -> Self {
+        Self {
+            x: "x".to_string(),
+            x_scale: 6,
+            y: "y".to_string(),
+            y_scale: 6,
+            e: "e".to_string(),
+            e_scale: 1,
+            s: "S".to_string(),
+            r: "R".to_string(),
+            t: None,
+        }
+    }
+
+    // Builds a schema from (field, column name, scale) triples. Each field may be listed once, and once every field
+    // has its column no two fields may read the same column.
+    pub fn with_columns<'a, I>(columns: I) -> Result<Self, TracklibRwgpsError>
+    where
+        I: IntoIterator<Item = (&'a str, &'a str, Option<u8>)>,
+    {
+        let mut point_schema = Self::new();
+        let mut fields = HashSet::new();
+        for (field, name, scale) in columns {
+            point_schema.set_column(field, name, scale)?;
+            if !fields.insert(field) {
+                return Err(TracklibRwgpsError::InvalidPointSchema(format!(
+                    "Point schema field '{field}' is given more than once"
+                )));
+            }
+        }
+
+        let point_fields = [
+            &point_schema.x,
+            &point_schema.y,
+            &point_schema.e,
+            &point_schema.s,
+            &point_schema.r,
+        ];
+        let mut names = HashSet::new();
+        for name in point_fields.into_iter().chain(&point_schema.t) {
+            if !names.insert(name) {
+                return Err(TracklibRwgpsError::InvalidPointSchema(format!(
+                    "Point schema column '{name}' is used by more than one field"
+                )));
+            }
+        }
+
+        Ok(point_schema)
+    }
+
+    fn set_column(&mut self, field: &str, name: &str, scale: Option<u8>) -> Result<(), TracklibRwgpsError> {
+        let name = name.to_string();
+        match (field, scale) {
+            ("x", scale) => {
+                self.x = name;
+                self.x_scale = scale.unwrap_or(self.x_scale);
+            }
+            ("y", scale) => {
+                self.y = name;
+                self.y_scale = scale.unwrap_or(self.y_scale);
+            }
+            ("e", scale) => {
+                self.e = name;
+                self.e_scale = scale.unwrap_or(self.e_scale);
+            }
+            ("S", None) => self.s = name,
+            ("R", None) => self.r = name,
+            ("t", None) => self.t = Some(name),
+            ("S" | "R" | "t", Some(_)) => {
+                return Err(TracklibRwgpsError::InvalidPointSchema(format!(
+                    "Point schema field '{field}' does not allow a scale"
+                )))
+            }
+            _ => {
+                return Err(TracklibRwgpsError::InvalidPointSchema(format!(
+                    "Point schema field '{field}' is not valid"
+                )))
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn x(&self) -> &str {
+        &self.x
+    }
+
+    pub fn y(&self) -> &str {
+        &self.y
+    }
+
+    pub fn e(&self) -> &str {
+        &self.e
+    }
+
+    pub fn s(&self) -> &str {
+        &self.s
+    }
+
+    pub fn r(&self) -> &str {
+        &self.r
+    }
+
+    // Sections often have no time column, so one is only read when it was given a name
+    pub fn t(&self) -> Option<&str> {
+        self.t.as_deref()
+    }
+
+    pub fn schema(&self) -> Schema {
+        let mut fields = vec![
+            FieldDefinition::new(&self.x, DataType::F64 { scale: self.x_scale }),
+            FieldDefinition::new(&self.y, DataType::F64 { scale: self.y_scale }),
+            FieldDefinition::new(&self.e, DataType::F64 { scale: self.e_scale }),
+            FieldDefinition::new(&self.s, DataType::U64),
+            FieldDefinition::new(&self.r, DataType::U64),
+        ];
+        if let Some(t) = &self.t {
+            fields.push(FieldDefinition::new(t, DataType::I64));
+        }
+
+        Schema::with_fields(fields)
+    }
+}
+
+impl Default for PointSchema {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_columns() {
+        let point_schema =
+            PointSchema::with_columns([("x", "lng", None), ("y", "lat", Some(7)), ("S", "surface", None)]).unwrap();
+
+        assert_eq!(point_schema.x(), "lng");
+        assert_eq!(point_schema.y(), "lat");
+        assert_eq!(point_schema.e(), "e");
+        assert_eq!(point_schema.s(), "surface");
+        assert_eq!(point_schema.r(), "R");
+        assert_eq!(point_schema.t(), None);
+
+        let point_schema = PointSchema::with_columns([("t", "time", None)]).unwrap();
+        assert_eq!(point_schema.t(), Some("time"));
+
+        // Fields can trade columns, as long as no column ends up read twice
+        let point_schema = PointSchema::with_columns([("x", "y", None), ("y", "x", None)]).unwrap();
+        assert_eq!(point_schema.x(), "y");
+        assert_eq!(point_schema.y(), "x");
+
+        for columns in [
+            vec![("R", "road_class", Some(1))],
+            vec![("z", "altitude", None)],
+            vec![("t", "time", Some(3))],
+            vec![("x", "lng", None), ("x", "lon", None)],
+            vec![("x", "lng", None), ("y", "lng", None)],
+            vec![("t", "e", None)],
+            vec![("x", "y", None)],
+        ] {
+            assert!(matches!(
+                PointSchema::with_columns(columns),
+                Err(TracklibRwgpsError::InvalidPointSchema(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_schema_time_column_is_opt_in() {
+        let names = |point_schema: &PointSchema| {
+            point_schema
+                .schema()
+                .fields()
+                .iter()
+                .map(|field| field.name().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&PointSchema::new()), ["x", "y", "e", "S", "R"]);
+
+        let point_schema = PointSchema::with_columns([("t", "time", None)]).unwrap();
+        assert_eq!(names(&point_schema), ["x", "y", "e", "S", "R", "time"]);
+    }
+}
@@ -1,13 +1,174 @@
-use super::rust::simplify_points;
-use crate::geometry::{reader_to_points, IrrelevantPointsBehavior};
+use super::rust::{
+    simplify_points, simplify_points_levels, simplify_points_significance, SimplifyAlgorithm, SimplifyElevation,
+    SimplifyOptions, SimplifyUnits, SurfaceSmoothing,
+};
+use crate::error::ruby::raise;
+use crate::error::rust::TracklibRwgpsError;
+use crate::geometry::{section_to_points, with_section_reader, IrrelevantPointsBehavior, Point};
 use crate::polyline::ruby::PolylineOptions;
 use crate::polyline::rust::polyline_encode;
+use crate::ruby::{f64_from_ruby, key_material_from_ruby};
+use crate::schema::ruby::RubyPointSchema;
+use crate::schema::rust::PointSchema;
 use crate::surface::ruby::RubySurfaceMapping;
 use itertools::Itertools;
-use rutie::{methods, module, AnyObject, Array, Class, Float, Hash, Integer, NilClass, Object, RString, VM};
+use rutie::{
+    class, methods, module, wrappable_struct, AnyObject, Array, Boolean, Float, Hash, Integer, Module, NilClass,
+    Object, RString, VerifiedObject, VM,
+};
 use std::collections::HashSet;
 use tracklib::read::section::SectionRead;
 
+pub struct WrappableSimplifyOptions {
+    opts: SimplifyOptions,
+}
+
+wrappable_struct!(
+    WrappableSimplifyOptions,
+    SimplifyOptionsWrapper,
+    SIMPLIFY_OPTIONS_WRAPPER_INSTANCE
+);
+
+class!(RubySimplifyOptions);
+
+methods!(
+    RubySimplifyOptions,
+    rtself,
+    fn simplify_options_new(tolerance: Float) -> AnyObject {
+        let rust_tolerance = tolerance.map_err(VM::raise_ex).unwrap().to_f64();
+
+        Module::from_existing("TracklibRwgps")
+            .get_nested_class("SimplifyOptions")
+            .wrap_data(
+                WrappableSimplifyOptions {
+                    opts: SimplifyOptions::new(rust_tolerance),
+                },
+                &*SIMPLIFY_OPTIONS_WRAPPER_INSTANCE,
+            )
+    },
+    fn simplify_options_set_algorithm(algorithm: RString) -> NilClass {
+        let rust_algorithm = algorithm
+            .map_err(VM::raise_ex)
+            .unwrap()
+            .to_str()
+            .parse::<SimplifyAlgorithm>()
+            .map_err(raise)
+            .unwrap();
+        let opts = &mut rtself.get_data_mut(&*SIMPLIFY_OPTIONS_WRAPPER_INSTANCE).opts;
+        opts.set_algorithm(rust_algorithm);
+
+        NilClass::new()
+    },
+    fn simplify_options_set_units(units: RString) -> NilClass {
+        let rust_units = units
+            .map_err(VM::raise_ex)
+            .unwrap()
+            .to_str()
+            .parse::<SimplifyUnits>()
+            .map_err(raise)
+            .unwrap();
+        let opts = &mut rtself.get_data_mut(&*SIMPLIFY_OPTIONS_WRAPPER_INSTANCE).opts;
+        opts.set_units(rust_units);
+
+        NilClass::new()
+    },
+    fn simplify_options_set_elevation(elevation: RString) -> NilClass {
+        let rust_elevation = elevation
+            .map_err(VM::raise_ex)
+            .unwrap()
+            .to_str()
+            .parse::<SimplifyElevation>()
+            .map_err(raise)
+            .unwrap();
+        let opts = &mut rtself.get_data_mut(&*SIMPLIFY_OPTIONS_WRAPPER_INSTANCE).opts;
+        opts.set_elevation(rust_elevation);
+
+        NilClass::new()
+    },
+    fn simplify_options_set_max_points(max_points: Integer) -> NilClass {
+        let ruby_max_points = max_points.map_err(VM::raise_ex).unwrap().to_i64();
+        let rust_max_points = usize::try_from(ruby_max_points)
+            .ok()
+            .filter(|max_points| *max_points >= 1)
+            .ok_or_else(|| {
+                TracklibRwgpsError::InvalidSimplifyOption(format!(
+                    "Simplification max_points {ruby_max_points} is not valid, it must be at least 1"
+                ))
+            })
+            .map_err(raise)
+            .unwrap();
+        let opts = &mut rtself.get_data_mut(&*SIMPLIFY_OPTIONS_WRAPPER_INSTANCE).opts;
+        opts.set_max_points(rust_max_points);
+
+        NilClass::new()
+    },
+    fn simplify_options_set_surface_smoothing(mode: RString, value: AnyObject) -> NilClass {
+        let rust_mode = mode.map_err(VM::raise_ex).unwrap();
+        let rust_value = value.ok().filter(|value| !value.is_nil()).map(f64_from_ruby);
+        let rust_smoothing = SurfaceSmoothing::from_mode(rust_mode.to_str(), rust_value)
+            .map_err(raise)
+            .unwrap();
+        let opts = &mut rtself.get_data_mut(&*SIMPLIFY_OPTIONS_WRAPPER_INSTANCE).opts;
+        opts.set_surface_smoothing(rust_smoothing);
+
+        NilClass::new()
+    },
+    fn simplify_options_set_keep_road_class_changes(keep_road_class_changes: Boolean) -> NilClass {
+        let rust_keep_road_class_changes = keep_road_class_changes.map_err(VM::raise_ex).unwrap().to_bool();
+        let opts = &mut rtself.get_data_mut(&*SIMPLIFY_OPTIONS_WRAPPER_INSTANCE).opts;
+        opts.set_keep_road_class_changes(rust_keep_road_class_changes);
+
+        NilClass::new()
+    },
+    fn simplify_options_to_s() -> RString {
+        let opts = &rtself.get_data(&*SIMPLIFY_OPTIONS_WRAPPER_INSTANCE).opts;
+
+        RString::new_utf8(&format!("{:?}", opts))
+    }
+);
+
+impl RubySimplifyOptions {
+    pub(crate) fn inner(&self) -> &SimplifyOptions {
+        &self.get_data(&*SIMPLIFY_OPTIONS_WRAPPER_INSTANCE).opts
+    }
+}
+
+impl VerifiedObject for RubySimplifyOptions {
+    fn is_correct_type<T: Object>(object: &T) -> bool {
+        object.class() == Module::from_existing("TracklibRwgps").get_nested_class("SimplifyOptions")
+    }
+
+    fn error_message() -> &'static str {
+        "Error converting to SimplifyOptions"
+    }
+}
+
+// Simplification functions accept either a bare tolerance or a SimplifyOptions instance
+pub(crate) fn simplify_options_from_ruby(tolerance: AnyObject) -> SimplifyOptions {
+    match tolerance.try_convert_to::<RubySimplifyOptions>() {
+        Ok(ruby_opts) => ruby_opts.inner().clone(),
+        Err(_) => {
+            let rust_tolerance = tolerance.try_convert_to::<Float>().map_err(VM::raise_ex).unwrap().to_f64();
+            SimplifyOptions::new(rust_tolerance)
+        }
+    }
+}
+
+// Exports simplify only when given a tolerance, and nil is the same as leaving it out
+pub(crate) fn optional_simplify_options_from_ruby(tolerance: Option<AnyObject>) -> Option<SimplifyOptions> {
+    tolerance
+        .filter(|tolerance| !tolerance.is_nil())
+        .map(simplify_options_from_ruby)
+}
+
+// The levels functions take their tolerances as an array, and any other settings from an optional SimplifyOptions
+fn tolerances_from_ruby(tolerances: Array) -> Vec<f64> {
+    tolerances
+        .into_iter()
+        .map(|tolerance| tolerance.try_convert_to::<Float>().map_err(VM::raise_ex).unwrap().to_f64())
+        .collect()
+}
+
 module!(TracklibRwgps);
 
 methods!(
@@ -17,16 +178,17 @@ methods!(
         track_reader: ruby_tracklib::read::TrackReader,
         index: Integer,
         mapping: RubySurfaceMapping,
-        tolerance: Float,
+        tolerance: AnyObject,
         polyline_opts: PolylineOptions,
-        key_material: RString) -> RString {
+        key_material: AnyObject,
+        point_schema: RubyPointSchema) -> RString {
         let ruby_track_reader = track_reader.map_err(VM::raise_ex).unwrap();
 
-        let rust_tolerance = tolerance.map_err(VM::raise_ex).unwrap().to_f64();
+        let rust_simplify_opts = simplify_options_from_ruby(tolerance.map_err(VM::raise_ex).unwrap());
 
         let ruby_index = index.map_err(VM::raise_ex).unwrap();
         let rust_index = usize::try_from(ruby_index.to_u64())
-            .map_err(|_| VM::raise(Class::from_existing("Exception"), "u64 != usize"))
+            .map_err(|_| raise(TracklibRwgpsError::SectionMissing))
             .unwrap();
 
         let ruby_polyline_opts = polyline_opts.map_err(VM::raise_ex).unwrap();
@@ -35,134 +197,272 @@ methods!(
         let ruby_mapping = mapping.map_err(VM::raise_ex).unwrap();
         let rust_mapping = ruby_mapping.inner();
 
+        let ruby_key_material = key_material.ok().and_then(key_material_from_ruby);
+        let rust_key_material = ruby_key_material.as_ref().map(|key| key.to_bytes_unchecked());
+
+        let rust_point_schema = point_schema
+            .ok()
+            .map_or_else(PointSchema::new, |ruby_point_schema| ruby_point_schema.inner().clone());
+
         ruby_track_reader.with_track_reader(|track_reader| {
-            track_reader
+            let mut section = track_reader
                 .section(rust_index)
-                .map(|section| {
-                    let schema = tracklib::schema::Schema::with_fields(vec![
-                        tracklib::schema::FieldDefinition::new("x", tracklib::schema::DataType::F64 { scale: 6 }),
-                        tracklib::schema::FieldDefinition::new("y", tracklib::schema::DataType::F64 { scale: 6 }),
-                        tracklib::schema::FieldDefinition::new("e", tracklib::schema::DataType::F64 { scale: 1 }),
-                        tracklib::schema::FieldDefinition::new("S", tracklib::schema::DataType::U64),
-                        tracklib::schema::FieldDefinition::new("R", tracklib::schema::DataType::U64),
-                    ]);
-
-                    match section {
-                        tracklib::read::section::Section::Standard(section) => {
-                            let section_reader = section
-                                .reader_for_schema(&schema)
-                                .map_err(|e| VM::raise(Class::from_existing("Exception"), &format!("{}", e)))
-                                .unwrap();
-                            let points = reader_to_points(section_reader, IrrelevantPointsBehavior::Ignore);
-                            let simplified_indexes = simplify_points(&points, &rust_mapping, rust_tolerance);
-                            let simplified_points = simplified_indexes
-                                .into_iter()
-                                .sorted()
-                                .map(|index| points[index].clone())
-                                .collect::<Vec<_>>();
-
-                            RString::from(polyline_encode(&simplified_points, rust_polyline_opts))
-                        }
-                        tracklib::read::section::Section::Encrypted(mut section) => {
-                            let ruby_key_material = key_material.map_err(VM::raise_ex).unwrap();
-                            let rust_key_material = ruby_key_material.to_bytes_unchecked();
-
-                            let section_reader = section
-                                .reader_for_schema(rust_key_material, &schema)
-                                .map_err(|e| VM::raise(Class::from_existing("Exception"), &format!("{}", e)))
-                                .unwrap();
-                            let points = reader_to_points(section_reader, IrrelevantPointsBehavior::Ignore);
-                            let simplified_indexes = simplify_points(&points, &rust_mapping, rust_tolerance);
-                            let simplified_points = simplified_indexes
-                                .into_iter()
-                                .sorted()
-                                .map(|index| points[index].clone())
-                                .collect::<Vec<_>>();
-
-                            RString::from(polyline_encode(&simplified_points, rust_polyline_opts))
-                        }
-                    }
-                })
-                .ok_or_else(|| VM::raise(Class::from_existing("Exception"), "Section does not exist"))
-                .unwrap()
+                .ok_or(TracklibRwgpsError::SectionMissing)
+                .map_err(raise)
+                .unwrap();
+            let points = section_to_points(
+                &mut section,
+                rust_key_material,
+                &rust_point_schema,
+                None,
+                IrrelevantPointsBehavior::Ignore,
+            )
+            .map_err(raise)
+            .unwrap();
+            let simplified_indexes = simplify_points(&points, &rust_mapping, &rust_simplify_opts);
+            let simplified_points = simplified_indexes
+                .into_iter()
+                .sorted()
+                .map(|index| points[index].clone())
+                .collect::<Vec<_>>();
+
+            RString::from(polyline_encode(&simplified_points, rust_polyline_opts))
+        })
+    },
+    fn simplify_section_data_simplified_polyline_levels(
+        track_reader: ruby_tracklib::read::TrackReader,
+        index: Integer,
+        mapping: RubySurfaceMapping,
+        tolerances: Array,
+        polyline_opts: PolylineOptions,
+        simplify_opts: RubySimplifyOptions,
+        key_material: AnyObject,
+        point_schema: RubyPointSchema) -> Array {
+        let ruby_track_reader = track_reader.map_err(VM::raise_ex).unwrap();
+
+        let rust_tolerances = tolerances_from_ruby(tolerances.map_err(VM::raise_ex).unwrap());
+        let rust_simplify_opts = simplify_opts
+            .ok()
+            .map_or_else(|| SimplifyOptions::new(0.0), |ruby_opts| ruby_opts.inner().clone());
+
+        let ruby_index = index.map_err(VM::raise_ex).unwrap();
+        let rust_index = usize::try_from(ruby_index.to_u64())
+            .map_err(|_| raise(TracklibRwgpsError::SectionMissing))
+            .unwrap();
+
+        let ruby_polyline_opts = polyline_opts.map_err(VM::raise_ex).unwrap();
+        let rust_polyline_opts = ruby_polyline_opts.inner();
+
+        let ruby_mapping = mapping.map_err(VM::raise_ex).unwrap();
+        let rust_mapping = ruby_mapping.inner();
+
+        let ruby_key_material = key_material.ok().and_then(key_material_from_ruby);
+        let rust_key_material = ruby_key_material.as_ref().map(|key| key.to_bytes_unchecked());
+
+        let rust_point_schema = point_schema
+            .ok()
+            .map_or_else(PointSchema::new, |ruby_point_schema| ruby_point_schema.inner().clone());
+
+        ruby_track_reader.with_track_reader(|track_reader| {
+            let mut section = track_reader
+                .section(rust_index)
+                .ok_or(TracklibRwgpsError::SectionMissing)
+                .map_err(raise)
+                .unwrap();
+            let points = section_to_points(
+                &mut section,
+                rust_key_material,
+                &rust_point_schema,
+                None,
+                IrrelevantPointsBehavior::Ignore,
+            )
+            .map_err(raise)
+            .unwrap();
+
+            let mut polylines = Array::new();
+            let levels = simplify_points_levels(&points, &rust_mapping, &rust_simplify_opts, &rust_tolerances);
+            for simplified_indexes in levels {
+                let simplified_points = simplified_indexes
+                    .into_iter()
+                    .sorted()
+                    .map(|index| points[index].clone())
+                    .collect::<Vec<_>>();
+                polylines.push(RString::from(polyline_encode(&simplified_points, rust_polyline_opts)));
+            }
+            polylines
+        })
+    },
+    fn simplify_section_simplified_indexes_levels(
+        track_reader: ruby_tracklib::read::TrackReader,
+        index: Integer,
+        mapping: RubySurfaceMapping,
+        tolerances: Array,
+        simplify_opts: RubySimplifyOptions,
+        key_material: AnyObject,
+        point_schema: RubyPointSchema) -> Array {
+        let ruby_track_reader = track_reader.map_err(VM::raise_ex).unwrap();
+
+        let rust_tolerances = tolerances_from_ruby(tolerances.map_err(VM::raise_ex).unwrap());
+        let rust_simplify_opts = simplify_opts
+            .ok()
+            .map_or_else(|| SimplifyOptions::new(0.0), |ruby_opts| ruby_opts.inner().clone());
+
+        let ruby_index = index.map_err(VM::raise_ex).unwrap();
+        let rust_index = usize::try_from(ruby_index.to_u64())
+            .map_err(|_| raise(TracklibRwgpsError::SectionMissing))
+            .unwrap();
+
+        let ruby_mapping = mapping.map_err(VM::raise_ex).unwrap();
+        let rust_mapping = ruby_mapping.inner();
+
+        let ruby_key_material = key_material.ok().and_then(key_material_from_ruby);
+        let rust_key_material = ruby_key_material.as_ref().map(|key| key.to_bytes_unchecked());
+
+        let rust_point_schema = point_schema
+            .ok()
+            .map_or_else(PointSchema::new, |ruby_point_schema| ruby_point_schema.inner().clone());
+
+        ruby_track_reader.with_track_reader(|track_reader| {
+            let mut section = track_reader
+                .section(rust_index)
+                .ok_or(TracklibRwgpsError::SectionMissing)
+                .map_err(raise)
+                .unwrap();
+            let points = section_to_points(
+                &mut section,
+                rust_key_material,
+                &rust_point_schema,
+                None,
+                IrrelevantPointsBehavior::Count,
+            )
+            .map_err(raise)
+            .unwrap();
+
+            let mut ruby_levels = Array::new();
+            let levels = simplify_points_levels(&points, &rust_mapping, &rust_simplify_opts, &rust_tolerances);
+            for simplified_indexes in levels {
+                let mut indexes = Array::new();
+                for index in simplified_indexes.into_iter().sorted() {
+                    indexes.push(Integer::from(index as u64));
+                }
+                ruby_levels.push(indexes);
+            }
+            ruby_levels
+        })
+    },
+    fn simplify_section_simplified_significance(
+        track_reader: ruby_tracklib::read::TrackReader,
+        index: Integer,
+        mapping: RubySurfaceMapping,
+        simplify_opts: RubySimplifyOptions,
+        key_material: AnyObject,
+        point_schema: RubyPointSchema) -> Array {
+        let ruby_track_reader = track_reader.map_err(VM::raise_ex).unwrap();
+
+        let rust_simplify_opts = simplify_opts
+            .ok()
+            .map_or_else(|| SimplifyOptions::new(0.0), |ruby_opts| ruby_opts.inner().clone());
+
+        let ruby_index = index.map_err(VM::raise_ex).unwrap();
+        let rust_index = usize::try_from(ruby_index.to_u64())
+            .map_err(|_| raise(TracklibRwgpsError::SectionMissing))
+            .unwrap();
+
+        let ruby_mapping = mapping.map_err(VM::raise_ex).unwrap();
+        let rust_mapping = ruby_mapping.inner();
+
+        let ruby_key_material = key_material.ok().and_then(key_material_from_ruby);
+        let rust_key_material = ruby_key_material.as_ref().map(|key| key.to_bytes_unchecked());
+
+        let rust_point_schema = point_schema
+            .ok()
+            .map_or_else(PointSchema::new, |ruby_point_schema| ruby_point_schema.inner().clone());
+
+        ruby_track_reader.with_track_reader(|track_reader| {
+            let mut section = track_reader
+                .section(rust_index)
+                .ok_or(TracklibRwgpsError::SectionMissing)
+                .map_err(raise)
+                .unwrap();
+            let points = section_to_points(
+                &mut section,
+                rust_key_material,
+                &rust_point_schema,
+                None,
+                IrrelevantPointsBehavior::Count,
+            )
+            .map_err(raise)
+            .unwrap();
+
+            let rows = match section {
+                tracklib::read::section::Section::Standard(ref section) => section.rows(),
+                tracklib::read::section::Section::Encrypted(ref section) => section.rows(),
+            };
+
+            // One entry per row, with nil for rows that aren't points
+            let mut removed_at = vec![None; rows];
+            for (index, tolerance) in simplify_points_significance(&points, &rust_mapping, &rust_simplify_opts) {
+                removed_at[index] = Some(tolerance);
+            }
+
+            let mut significance = Array::new();
+            for tolerance in removed_at {
+                match tolerance {
+                    Some(tolerance) => significance.push(Float::new(tolerance)),
+                    None => significance.push(NilClass::new()),
+                };
+            }
+            significance
         })
     },
     fn simplify_section_data_simplified(
         track_reader: ruby_tracklib::read::TrackReader,
         index: Integer,
         mapping: RubySurfaceMapping,
-        tolerance: Float,
-        key_material: RString) -> Array {
+        tolerance: AnyObject,
+        key_material: AnyObject,
+        point_schema: RubyPointSchema) -> Array {
         let ruby_track_reader = track_reader.map_err(VM::raise_ex).unwrap();
 
-        let rust_tolerance = tolerance.map_err(VM::raise_ex).unwrap().to_f64();
+        let rust_simplify_opts = simplify_options_from_ruby(tolerance.map_err(VM::raise_ex).unwrap());
 
         let ruby_index = index.map_err(VM::raise_ex).unwrap();
         let rust_index = usize::try_from(ruby_index.to_u64())
-            .map_err(|_| VM::raise(Class::from_existing("Exception"), "u64 != usize"))
+            .map_err(|_| raise(TracklibRwgpsError::SectionMissing))
             .unwrap();
 
         let ruby_mapping = mapping.map_err(VM::raise_ex).unwrap();
         let rust_mapping = ruby_mapping.inner();
 
+        let ruby_key_material = key_material.ok().and_then(key_material_from_ruby);
+        let rust_key_material = ruby_key_material.as_ref().map(|key| key.to_bytes_unchecked());
+
+        let rust_point_schema = point_schema
+            .ok()
+            .map_or_else(PointSchema::new, |ruby_point_schema| ruby_point_schema.inner().clone());
+
         ruby_track_reader.with_track_reader(|track_reader| {
-            track_reader
+            let mut section = track_reader
                 .section(rust_index)
-                .map(|section| {
-                    let schema = tracklib::schema::Schema::with_fields(vec![
-                        tracklib::schema::FieldDefinition::new("x", tracklib::schema::DataType::F64 { scale: 6 }),
-                        tracklib::schema::FieldDefinition::new("y", tracklib::schema::DataType::F64 { scale: 6 }),
-                        tracklib::schema::FieldDefinition::new("e", tracklib::schema::DataType::F64 { scale: 1 }),
-                        tracklib::schema::FieldDefinition::new("S", tracklib::schema::DataType::U64),
-                        tracklib::schema::FieldDefinition::new("R", tracklib::schema::DataType::U64),
-                    ]);
-
-                    match section {
-                        tracklib::read::section::Section::Standard(section) => {
-                            let section_reader_for_simplification = section
-                                .reader_for_schema(&schema)
-                                .map_err(|e| VM::raise(Class::from_existing("Exception"), &format!("{}", e)))
-                                .unwrap();
-                            let points =
-                                reader_to_points(section_reader_for_simplification, IrrelevantPointsBehavior::Count);
-                            let simplified_indexes = simplify_points(&points, &rust_mapping, rust_tolerance);
-
-                            let section_reader_for_serialization = section
-                                .reader()
-                                .map_err(|e| VM::raise(Class::from_existing("Exception"), &format!("{}", e)))
-                                .unwrap();
-
-                            reader_with_indexes_to_array_of_hashes(
-                                section_reader_for_serialization,
-                                &simplified_indexes,
-                            )
-                        }
-                        tracklib::read::section::Section::Encrypted(mut section) => {
-                            let ruby_key_material = key_material.map_err(VM::raise_ex).unwrap();
-                            let rust_key_material = ruby_key_material.to_bytes_unchecked();
-
-                            let section_reader_for_simplification = section
-                                .reader_for_schema(rust_key_material, &schema)
-                                .map_err(|e| VM::raise(Class::from_existing("Exception"), &format!("{}", e)))
-                                .unwrap();
-                            let points =
-                                reader_to_points(section_reader_for_simplification, IrrelevantPointsBehavior::Count);
-                            let simplified_indexes = simplify_points(&points, &rust_mapping, rust_tolerance);
-
-                            let section_reader_for_serialization = section
-                                .reader(rust_key_material)
-                                .map_err(|e| VM::raise(Class::from_existing("Exception"), &format!("{}", e)))
-                                .unwrap();
-
-                            reader_with_indexes_to_array_of_hashes(
-                                section_reader_for_serialization,
-                                &simplified_indexes,
-                            )
-                        }
-                    }
-                })
-                .ok_or_else(|| VM::raise(Class::from_existing("Exception"), "Section does not exist"))
-                .unwrap()
+                .ok_or(TracklibRwgpsError::SectionMissing)
+                .map_err(raise)
+                .unwrap();
+            let points = section_to_points(
+                &mut section,
+                rust_key_material,
+                &rust_point_schema,
+                None,
+                IrrelevantPointsBehavior::Count,
+            )
+            .map_err(raise)
+            .unwrap();
+            let simplified_indexes = simplify_points(&points, &rust_mapping, &rust_simplify_opts);
+
+            with_section_reader(&mut section, None, rust_key_material, |section_reader_for_serialization| {
+                reader_with_indexes_to_array_of_hashes(section_reader_for_serialization, &points, &simplified_indexes)
+            })
+            .map_err(raise)
+            .unwrap()
         })
     },
     fn simplify_section_column_simplified(
@@ -170,114 +470,92 @@ methods!(
         index: Integer,
         column_name: RString,
         mapping: RubySurfaceMapping,
-        tolerance: Float,
-        key_material: RString) -> AnyObject {
+        tolerance: AnyObject,
+        key_material: AnyObject,
+        point_schema: RubyPointSchema) -> AnyObject {
         let ruby_track_reader = track_reader.map_err(VM::raise_ex).unwrap();
 
-        let rust_tolerance = tolerance.map_err(VM::raise_ex).unwrap().to_f64();
+        let rust_simplify_opts = simplify_options_from_ruby(tolerance.map_err(VM::raise_ex).unwrap());
 
         let ruby_index = index.map_err(VM::raise_ex).unwrap();
         let rust_index = usize::try_from(ruby_index.to_u64())
-            .map_err(|_| VM::raise(Class::from_existing("Exception"), "u64 != usize"))
+            .map_err(|_| raise(TracklibRwgpsError::SectionMissing))
             .unwrap();
 
+        let ruby_field_name = column_name.map_err(VM::raise_ex).unwrap();
+        let field_name = ruby_field_name.to_str();
+
         let ruby_mapping = mapping.map_err(VM::raise_ex).unwrap();
         let rust_mapping = ruby_mapping.inner();
 
+        let ruby_key_material = key_material.ok().and_then(key_material_from_ruby);
+        let rust_key_material = ruby_key_material.as_ref().map(|key| key.to_bytes_unchecked());
+
+        let rust_point_schema = point_schema
+            .ok()
+            .map_or_else(PointSchema::new, |ruby_point_schema| ruby_point_schema.inner().clone());
+
         ruby_track_reader.with_track_reader(|track_reader| {
-            track_reader
+            let mut section = track_reader
                 .section(rust_index)
-                .map(|section| {
-                    let ruby_field_name = column_name.map_err(VM::raise_ex).unwrap();
-                    let field_name = ruby_field_name.to_str();
-
-                    let schema = match section {
-                        tracklib::read::section::Section::Standard(ref section) => section.schema(),
-                        tracklib::read::section::Section::Encrypted(ref section) => section.schema(),
-                    };
-                    let maybe_field_def = schema.fields().iter().find(|field_def| field_def.name() == field_name);
-
-                    if let Some(field_def) = maybe_field_def {
-                        let schema_for_serialization = tracklib::schema::Schema::with_fields(vec![field_def.clone()]);
-
-                        let schema_for_simplification = tracklib::schema::Schema::with_fields(vec![
-                            tracklib::schema::FieldDefinition::new("x", tracklib::schema::DataType::F64 { scale: 6 }),
-                            tracklib::schema::FieldDefinition::new("y", tracklib::schema::DataType::F64 { scale: 6 }),
-                            tracklib::schema::FieldDefinition::new("e", tracklib::schema::DataType::F64 { scale: 1 }),
-                            tracklib::schema::FieldDefinition::new("S", tracklib::schema::DataType::U64),
-                            tracklib::schema::FieldDefinition::new("R", tracklib::schema::DataType::U64),
-                        ]);
-
-                        match section {
-                            tracklib::read::section::Section::Standard(section) => {
-                                let section_reader_for_simplification = section
-                                    .reader_for_schema(&schema_for_simplification)
-                                    .map_err(|e| VM::raise(Class::from_existing("Exception"), &format!("{}", e)))
-                                    .unwrap();
-                                let points = reader_to_points(
-                                    section_reader_for_simplification,
-                                    IrrelevantPointsBehavior::Count,
-                                );
-                                let simplified_indexes = simplify_points(&points, &rust_mapping, rust_tolerance);
-
-                                let section_reader_for_serialization = section
-                                    .reader_for_schema(&schema_for_serialization)
-                                    .map_err(|e| VM::raise(Class::from_existing("Exception"), &format!("{}", e)))
-                                    .unwrap();
-                                reader_with_indexes_to_single_column_array(
-                                    section_reader_for_serialization,
-                                    &simplified_indexes,
-                                )
-                                .to_any_object()
-                            }
-                            tracklib::read::section::Section::Encrypted(mut section) => {
-                                let ruby_key_material = key_material.map_err(VM::raise_ex).unwrap();
-                                let rust_key_material = ruby_key_material.to_bytes_unchecked();
-
-                                let section_reader_for_simplification = section
-                                    .reader_for_schema(rust_key_material, &schema)
-                                    .map_err(|e| VM::raise(Class::from_existing("Exception"), &format!("{}", e)))
-                                    .unwrap();
-                                let points = reader_to_points(
-                                    section_reader_for_simplification,
-                                    IrrelevantPointsBehavior::Count,
-                                );
-                                let simplified_indexes = simplify_points(&points, &rust_mapping, rust_tolerance);
-
-                                let section_reader_for_serialization = section
-                                    .reader_for_schema(rust_key_material, &schema_for_serialization)
-                                    .map_err(|e| VM::raise(Class::from_existing("Exception"), &format!("{}", e)))
-                                    .unwrap();
-                                reader_with_indexes_to_single_column_array(
-                                    section_reader_for_serialization,
-                                    &simplified_indexes,
-                                )
-                                .to_any_object()
-                            }
-                        }
-                    } else {
-                        NilClass::new().to_any_object()
-                    }
-                })
-                .ok_or_else(|| VM::raise(Class::from_existing("Exception"), "Section does not exist"))
+                .ok_or(TracklibRwgpsError::SectionMissing)
+                .map_err(raise)
+                .unwrap();
+
+            let schema = match section {
+                tracklib::read::section::Section::Standard(ref section) => section.schema(),
+                tracklib::read::section::Section::Encrypted(ref section) => section.schema(),
+            };
+            let maybe_field_def = schema.fields().iter().find(|field_def| field_def.name() == field_name);
+
+            if let Some(field_def) = maybe_field_def {
+                let schema_for_serialization = tracklib::schema::Schema::with_fields(vec![field_def.clone()]);
+
+                let points = section_to_points(
+                    &mut section,
+                    rust_key_material,
+                    &rust_point_schema,
+                    None,
+                    IrrelevantPointsBehavior::Count,
+                )
+                .map_err(raise)
+                .unwrap();
+                let simplified_indexes = simplify_points(&points, &rust_mapping, &rust_simplify_opts);
+
+                with_section_reader(
+                    &mut section,
+                    Some(&schema_for_serialization),
+                    rust_key_material,
+                    |section_reader_for_serialization| {
+                        reader_with_indexes_to_single_column_array(
+                            section_reader_for_serialization,
+                            &simplified_indexes,
+                            field_name,
+                        )
+                    },
+                )
+                .map_err(raise)
                 .unwrap()
+                .to_any_object()
+            } else {
+                NilClass::new().to_any_object()
+            }
         })
     }
 );
 
-fn reader_with_indexes_to_array_of_hashes(
+pub(crate) fn reader_with_indexes_to_array_of_hashes(
     mut reader: tracklib::read::section::reader::SectionReader,
+    points: &[Point],
     indexes: &HashSet<usize>,
-) -> Array {
+) -> Result<Array, TracklibRwgpsError> {
     let mut data_array = Array::new();
     let mut i = 0;
     while let Some(columniter) = reader.open_column_iter() {
         if indexes.contains(&i) {
             let mut row_hash = Hash::new();
             for row in columniter {
-                let (field_def, maybe_value) = row
-                    .map_err(|e| VM::raise(Class::from_existing("Exception"), &format!("{}", e)))
-                    .unwrap();
+                let (field_def, maybe_value) = row.map_err(TracklibRwgpsError::Decode)?;
 
                 if let Some(value) = maybe_value {
                     row_hash.store(
@@ -286,6 +564,16 @@ fn reader_with_indexes_to_array_of_hashes(
                     );
                 }
             }
+
+            // Speed isn't a column, it's derived from the distance and time since the previous point
+            let maybe_speed = points
+                .binary_search_by_key(&i, |point| point.index())
+                .ok()
+                .and_then(|point_index| points[point_index].speed());
+            if let Some(speed) = maybe_speed {
+                row_hash.store(RString::from("speed"), Float::new(speed));
+            }
+
             data_array.push(row_hash);
         } else {
             columniter.for_each(drop); // fully consume (and ignore) this row
@@ -293,23 +581,22 @@ fn reader_with_indexes_to_array_of_hashes(
         i += 1;
     }
 
-    data_array
+    Ok(data_array)
 }
 
 fn reader_with_indexes_to_single_column_array(
     mut reader: tracklib::read::section::reader::SectionReader,
     indexes: &HashSet<usize>,
-) -> Array {
+    field_name: &str,
+) -> Result<Array, TracklibRwgpsError> {
     let mut data_array = Array::new();
     let mut i = 0;
     while let Some(mut columniter) = reader.open_column_iter() {
         if indexes.contains(&i) {
             let (_field_def, maybe_value) = columniter
                 .next()
-                .ok_or_else(|| VM::raise(Class::from_existing("Exception"), "Missing field inside iterator"))
-                .unwrap()
-                .map_err(|e| VM::raise(Class::from_existing("Exception"), &format!("{}", e)))
-                .unwrap();
+                .ok_or_else(|| TracklibRwgpsError::MissingColumn(field_name.to_string()))?
+                .map_err(TracklibRwgpsError::Decode)?;
 
             let ruby_value = if let Some(value) = maybe_value {
                 ruby_tracklib::read::fieldvalue_to_ruby(value)
@@ -324,5 +611,5 @@ fn reader_with_indexes_to_single_column_array(
         i += 1;
     }
 
-    data_array
+    Ok(data_array)
 }
@@ -1,19 +1,408 @@
-use crate::geometry::{FarthestPoint, Point};
+use crate::error::rust::TracklibRwgpsError;
+use crate::geometry::{
+    project_to_meters, project_to_profile, triangle_area_2d, triangle_area_3d, FarthestPoint, Point,
+};
 use crate::surface::rust::SurfaceMapping;
-use std::collections::HashSet;
+use itertools::Itertools;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::str::FromStr;
 
-struct SurfaceGroupIter<'a, 'b> {
-    points: &'a [Point],
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SimplifyAlgorithm {
+    Rdp,
+    Visvalingam,
+}
+
+impl FromStr for SimplifyAlgorithm {
+    type Err = TracklibRwgpsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rdp" => Ok(SimplifyAlgorithm::Rdp),
+            "visvalingam" => Ok(SimplifyAlgorithm::Visvalingam),
+            _ => Err(TracklibRwgpsError::InvalidSimplifyOption(format!(
+                "Simplification algorithm '{s}' is not valid"
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SimplifyUnits {
+    Degrees,
+    Meters,
+}
+
+impl FromStr for SimplifyUnits {
+    type Err = TracklibRwgpsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "degrees" => Ok(SimplifyUnits::Degrees),
+            "meters" => Ok(SimplifyUnits::Meters),
+            _ => Err(TracklibRwgpsError::InvalidSimplifyOption(format!(
+                "Simplification units '{s}' are not valid"
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SimplifyElevation {
+    Ignore,
+    Profile,
+    Combined,
+}
+
+impl FromStr for SimplifyElevation {
+    type Err = TracklibRwgpsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ignore" => Ok(SimplifyElevation::Ignore),
+            "profile" => Ok(SimplifyElevation::Profile),
+            "combined" => Ok(SimplifyElevation::Combined),
+            _ => Err(TracklibRwgpsError::InvalidSimplifyOption(format!(
+                "Simplification elevation mode '{s}' is not valid"
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SurfaceSmoothing {
+    None,
+    // Runs of a surface group shorter than this many meters are merged into their longer neighbour
+    MinMeters(f64),
+    // Runs of a surface group with fewer points than this are merged into their longer neighbour
+    MinPoints(usize),
+    // Each point takes the most common surface group among this many points centred on it
+    Majority(usize),
+}
+
+impl SurfaceSmoothing {
+    pub fn from_mode(mode: &str, value: Option<f64>) -> Result<Self, TracklibRwgpsError> {
+        let invalid = || {
+            TracklibRwgpsError::InvalidSimplifyOption(format!(
+                "Surface smoothing value {value:?} is not valid for mode '{mode}'"
+            ))
+        };
+        let count = || match value {
+            Some(v) if v >= 1.0 && v.fract() == 0.0 && v <= usize::MAX as f64 => Ok(v as usize),
+            _ => Err(invalid()),
+        };
+
+        match mode {
+            "none" => Ok(SurfaceSmoothing::None),
+            "meters" => match value {
+                Some(v) if v >= 0.0 && v.is_finite() => Ok(SurfaceSmoothing::MinMeters(v)),
+                _ => Err(invalid()),
+            },
+            "points" => Ok(SurfaceSmoothing::MinPoints(count()?)),
+            "majority" => match count()? {
+                window if window % 2 == 1 => Ok(SurfaceSmoothing::Majority(window)),
+                _ => Err(invalid()),
+            },
+            _ => Err(TracklibRwgpsError::InvalidSimplifyOption(format!(
+                "Surface smoothing mode '{mode}' is not valid"
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SimplifyOptions {
+    tolerance: f64,
+    algorithm: SimplifyAlgorithm,
+    units: SimplifyUnits,
+    elevation: SimplifyElevation,
+    max_points: Option<usize>,
+    surface_smoothing: SurfaceSmoothing,
+    keep_road_class_changes: bool,
+}
+
+impl SimplifyOptions {
+    pub fn new(tolerance: f64) -> Self {
+        Self {
+            tolerance,
+            algorithm: SimplifyAlgorithm::Rdp,
+            units: SimplifyUnits::Degrees,
+            elevation: SimplifyElevation::Ignore,
+            max_points: None,
+            surface_smoothing: SurfaceSmoothing::None,
+            keep_road_class_changes: false,
+        }
+    }
+
+    pub fn set_algorithm(&mut self, algorithm: SimplifyAlgorithm) {
+        self.algorithm = algorithm;
+    }
+
+    pub fn set_units(&mut self, units: SimplifyUnits) {
+        self.units = units;
+    }
+
+    pub fn set_elevation(&mut self, elevation: SimplifyElevation) {
+        self.elevation = elevation;
+    }
+
+    pub fn set_max_points(&mut self, max_points: usize) {
+        self.max_points = Some(max_points);
+    }
+
+    pub fn set_surface_smoothing(&mut self, surface_smoothing: SurfaceSmoothing) {
+        self.surface_smoothing = surface_smoothing;
+    }
+
+    pub fn set_keep_road_class_changes(&mut self, keep_road_class_changes: bool) {
+        self.keep_road_class_changes = keep_road_class_changes;
+    }
+
+    pub fn tolerance(&self) -> f64 {
+        self.tolerance
+    }
+
+    pub fn algorithm(&self) -> SimplifyAlgorithm {
+        self.algorithm
+    }
+
+    pub fn units(&self) -> SimplifyUnits {
+        self.units
+    }
+
+    pub fn elevation(&self) -> SimplifyElevation {
+        self.elevation
+    }
+
+    pub fn max_points(&self) -> Option<usize> {
+        self.max_points
+    }
+
+    pub fn surface_smoothing(&self) -> SurfaceSmoothing {
+        self.surface_smoothing
+    }
+
+    pub fn keep_road_class_changes(&self) -> bool {
+        self.keep_road_class_changes
+    }
+}
+
+// A run of consecutive points sharing a surface group, as a half-open range of positions in the points slice
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct GroupRun<'b> {
+    pub(crate) group: Option<&'b String>,
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+}
+
+// Distance from the first point of a run to the first point of the next one, or to its own last point at the end of
+// the track
+pub(crate) fn run_distance(points: &[Point], run: &GroupRun) -> f64 {
+    points[run.end.min(points.len() - 1)].d() - points[run.start].d()
+}
+
+fn group_runs<'b>(groups: &[Option<&'b String>]) -> Vec<GroupRun<'b>> {
+    let mut runs: Vec<GroupRun> = Vec::new();
+    for (i, group) in groups.iter().enumerate() {
+        match runs.last_mut() {
+            Some(run) if run.group == *group => run.end = i + 1,
+            _ => runs.push(GroupRun {
+                group: *group,
+                start: i,
+                end: i + 1,
+            }),
+        }
+    }
+    runs
+}
+
+// Merges runs shorter than `min_length`, as measured by `length`, into their longer neighbour, shortest first, until
+// no short runs or only a single run are left
+pub(crate) fn merge_short_runs(runs: &mut Vec<GroupRun>, min_length: f64, length: impl Fn(&GroupRun) -> f64) {
+    let len = runs.len();
+    let mut prev = (0..len).map(|i| i.checked_sub(1)).collect::<Vec<_>>();
+    let mut next = (0..len).map(|i| Some(i + 1).filter(|n| *n < len)).collect::<Vec<_>>();
+    let mut lengths = runs.iter().map(&length).collect::<Vec<_>>();
+    let mut removed = vec![false; len];
+    let mut remaining = len;
+    let mut heap = (0..len)
+        .filter(|i| lengths[*i] < min_length)
+        .map(|i| Smallest {
+            key: lengths[i],
+            index: i,
+        })
+        .collect::<BinaryHeap<_>>();
+
+    while remaining > 1 {
+        let Some(Smallest { key, index }) = heap.pop() else {
+            break;
+        };
+        if removed[index] || key != lengths[index] {
+            continue; // stale entry, this run was merged away or grew after it was pushed
+        }
+
+        removed[index] = true;
+        remaining -= 1;
+        let (p, n) = (prev[index], next[index]);
+        if let Some(p) = p {
+            next[p] = n;
+        }
+        if let Some(n) = n {
+            prev[n] = p;
+        }
+
+        let (start, end) = (runs[index].start, runs[index].end);
+        let mut absorbing = match (p, n) {
+            (Some(p), Some(n)) if lengths[p] < lengths[n] => {
+                runs[n].start = start;
+                Some(n)
+            }
+            (Some(p), _) => {
+                runs[p].end = end;
+                Some(p)
+            }
+            (None, Some(n)) => {
+                runs[n].start = start;
+                Some(n)
+            }
+            (None, None) => None,
+        };
+
+        if let (Some(p), Some(n)) = (p, n) {
+            if runs[p].group == runs[n].group {
+                runs[p].end = runs[n].end;
+                removed[n] = true;
+                remaining -= 1;
+                next[p] = next[n];
+                if let Some(after) = next[n] {
+                    prev[after] = Some(p);
+                }
+                absorbing = Some(p);
+            }
+        }
+
+        if let Some(run) = absorbing {
+            lengths[run] = length(&runs[run]);
+            if lengths[run] < min_length {
+                heap.push(Smallest {
+                    key: lengths[run],
+                    index: run,
+                });
+            }
+        }
+    }
+
+    let mut removed = removed.into_iter();
+    runs.retain(|_| !removed.next().unwrap());
+}
+
+// Each group becomes the most common one within `window` points centred on it. A point keeps its own group unless
+// another one is strictly more common, so ties don't shift boundaries. Among equally common groups the one seen first
+// in the window wins. The window slides along keeping the positions of each group in it, so a point only costs as much
+// as the number of distinct groups in its window.
+fn majority_filter<'b>(groups: &[Option<&'b String>], window: usize) -> Vec<Option<&'b String>> {
+    let radius = window / 2;
+    let mut positions: HashMap<Option<&String>, VecDeque<usize>> = HashMap::new();
+    let mut end = 0;
+
+    (0..groups.len())
+        .map(|i| {
+            while end < (i + radius + 1).min(groups.len()) {
+                positions.entry(groups[end]).or_default().push_back(end);
+                end += 1;
+            }
+            if let Some(leaving) = i.checked_sub(radius + 1) {
+                let group_positions = positions.get_mut(&groups[leaving]).unwrap();
+                group_positions.pop_front();
+                if group_positions.is_empty() {
+                    positions.remove(&groups[leaving]);
+                }
+            }
+
+            let own_count = positions[&groups[i]].len();
+            let (most_common, most_common_positions) = positions
+                .iter()
+                .max_by(|(_, a), (_, b)| a.len().cmp(&b.len()).then_with(|| b.front().cmp(&a.front())))
+                .unwrap();
+            if most_common_positions.len() > own_count {
+                *most_common
+            } else {
+                groups[i]
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn surface_group_runs<'b>(
+    points: &[Point],
     mapping: &'b SurfaceMapping,
-    group: Option<&'b String>,
+    smoothing: SurfaceSmoothing,
+) -> Vec<GroupRun<'b>> {
+    let groups = points
+        .iter()
+        .map(|point| mapping.get_surface_group(point))
+        .collect::<Vec<_>>();
+
+    match smoothing {
+        SurfaceSmoothing::None => group_runs(&groups),
+        SurfaceSmoothing::Majority(window) => group_runs(&majority_filter(&groups, window)),
+        SurfaceSmoothing::MinMeters(min_meters) => {
+            let mut runs = group_runs(&groups);
+            merge_short_runs(&mut runs, min_meters, |run| run_distance(points, run));
+            runs
+        }
+        SurfaceSmoothing::MinPoints(min_points) => {
+            let mut runs = group_runs(&groups);
+            merge_short_runs(&mut runs, min_points as f64, |run| (run.end - run.start) as f64);
+            runs
+        }
+    }
+}
+
+pub(crate) struct SurfaceGroupIter<'a, 'b> {
+    points: &'a [Point],
+    runs: std::vec::IntoIter<GroupRun<'b>>,
 }
 
 impl<'a, 'b> SurfaceGroupIter<'a, 'b> {
-    fn new(points: &'a [Point], mapping: &'b SurfaceMapping) -> Self {
+    pub(crate) fn new(points: &'a [Point], mapping: &'b SurfaceMapping, smoothing: SurfaceSmoothing) -> Self {
+        Self {
+            points,
+            runs: surface_group_runs(points, mapping, smoothing).into_iter(),
+        }
+    }
+
+    // Further splits every partition wherever the road class changes, so both sides of the change become anchors
+    pub(crate) fn split_road_classes(self) -> Self {
+        let points = self.points;
+        let runs = self
+            .runs
+            .flat_map(|run| {
+                let mut split = Vec::new();
+                let mut start = run.start;
+                for i in run.start + 1..run.end {
+                    if points[i].r() != points[i - 1].r() {
+                        split.push(GroupRun {
+                            group: run.group,
+                            start,
+                            end: i,
+                        });
+                        start = i;
+                    }
+                }
+                split.push(GroupRun {
+                    group: run.group,
+                    start,
+                    end: run.end,
+                });
+                split
+            })
+            .collect::<Vec<_>>();
+
         Self {
             points,
-            mapping,
-            group: points.first().and_then(|point| mapping.get_surface_group(point)),
+            runs: runs.into_iter(),
         }
     }
 }
@@ -22,59 +411,306 @@ impl<'a, 'b> Iterator for SurfaceGroupIter<'a, 'b> {
     type Item = &'a [Point];
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut partition_len = 0;
-        for point in self.points.iter() {
-            let group = self.mapping.get_surface_group(point);
+        self.runs.next().map(|run| &self.points[run.start..run.end])
+    }
+}
 
-            if self.group == group {
-                partition_len += 1;
-            } else {
-                self.group = group;
-                break;
-            }
-        }
+// Splits points into one line per surface group partition, each running on to the first point of the next partition so
+// consecutive lines join up. Lines that would have fewer than two points are skipped.
+pub(crate) fn surface_group_lines<'a, 'b>(
+    points: &'a [Point],
+    mapping: &'b SurfaceMapping,
+    smoothing: SurfaceSmoothing,
+) -> Vec<(Option<&'b String>, &'a [Point])> {
+    surface_group_runs(points, mapping, smoothing)
+        .into_iter()
+        .map(|run| (run.group, &points[run.start..(run.end + 1).min(points.len())]))
+        .filter(|(_, line)| line.len() >= 2)
+        .collect()
+}
+
+// Returns each point's significance: the squared distance at which RDP split on it, or 0.0 for points it never
+// split on. Endpoints are always significant.
+fn stack_rdp(points: &[Point], tolerance_sq: f64, with_elevation: bool) -> Vec<f64> {
+    let mut significance = vec![0.0; points.len()];
+    if let (Some(first), Some(last)) = (significance.first_mut(), points.len().checked_sub(1)) {
+        *first = f64::INFINITY;
+        significance[last] = f64::INFINITY;
+    } else {
+        return significance;
+    }
+
+    let mut stack = Vec::new();
+    stack.push((0, points, f64::INFINITY));
 
-        if partition_len > 0 {
-            let (partition, new_points) = self.points.split_at(partition_len);
-            self.points = new_points;
-            Some(partition)
+    while let Some((offset, slice, parent_significance)) = stack.pop() {
+        let (farthest_index, farthest_dist) = if with_elevation {
+            slice.farthest_point_3d()
         } else {
-            None
+            slice.farthest_point()
+        };
+
+        if farthest_dist > tolerance_sq {
+            // A point is never more significant than the split that exposed it
+            let split_significance = farthest_dist.min(parent_significance);
+            significance[offset + farthest_index] = split_significance;
+            stack.push((offset, &slice[..=farthest_index], split_significance));
+            stack.push((offset + farthest_index, &slice[farthest_index..], split_significance));
         }
     }
+
+    significance
 }
 
-pub(crate) fn simplify_points(points: &[Point], mapping: &SurfaceMapping, tolerance: f64) -> HashSet<usize> {
-    fn stack_rdp(points: &[Point], tolerance_sq: f64) -> HashSet<usize> {
-        let mut anchors = HashSet::new();
-        let mut stack = Vec::new();
-        stack.push(points);
+// A heap entry for a point's effective area or a run's length
+#[derive(PartialEq)]
+struct Smallest {
+    key: f64,
+    index: usize,
+}
 
-        while let Some(slice) = stack.pop() {
-            let (farthest_index, farthest_dist) = slice.farthest_point();
+impl Eq for Smallest {}
 
-            if farthest_dist > tolerance_sq {
-                stack.push(&slice[..=farthest_index]);
-                stack.push(&slice[farthest_index..]);
-            } else {
-                anchors.insert(slice.first().unwrap().index());
-                anchors.insert(slice.last().unwrap().index());
+impl Ord for Smallest {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap is a max-heap, we want to pop the smallest key first, and the lowest index among equal keys
+        other
+            .key
+            .total_cmp(&self.key)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for Smallest {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Returns each point's significance: the effective area at which it was eliminated, along with the order it was
+// eliminated in. Points still standing once every remaining area exceeds `area_threshold` keep their current effective
+// area, endpoints are always significant. Points are eliminated by their triangle's area, the effective area only
+// carries the largest area eliminated so far forward, so it never decreases and ties are broken by the order.
+fn visvalingam(points: &[Point], area_threshold: f64, with_elevation: bool) -> Vec<(f64, usize)> {
+    let triangle_area = if with_elevation {
+        triangle_area_3d
+    } else {
+        triangle_area_2d
+    };
+    let len = points.len();
+    let mut significance = vec![(f64::INFINITY, len); len];
+    if len <= 2 {
+        return significance;
+    }
+
+    let mut prev = (0..len).map(|i| i.saturating_sub(1)).collect::<Vec<_>>();
+    let mut next = (0..len).map(|i| i + 1).collect::<Vec<_>>();
+    let mut areas = vec![f64::INFINITY; len];
+    let mut removed = vec![false; len];
+    let mut heap = BinaryHeap::with_capacity(len);
+    let mut max_area = 0.0_f64;
+    let mut order = 0;
+
+    for i in 1..len - 1 {
+        areas[i] = triangle_area(&points[i - 1], &points[i], &points[i + 1]);
+        heap.push(Smallest {
+            key: areas[i],
+            index: i,
+        });
+    }
+
+    while let Some(Smallest { key: area, index }) = heap.pop() {
+        if removed[index] || area != areas[index] {
+            continue; // stale entry, this point's area was recomputed after it was pushed
+        }
+        if area > area_threshold {
+            break;
+        }
+
+        removed[index] = true;
+        max_area = max_area.max(area);
+        significance[index] = (max_area, order);
+        order += 1;
+        let (p, n) = (prev[index], next[index]);
+        next[p] = n;
+        prev[n] = p;
+
+        for neighbor in [p, n] {
+            if neighbor != 0 && neighbor != len - 1 {
+                areas[neighbor] = triangle_area(&points[prev[neighbor]], &points[neighbor], &points[next[neighbor]]);
+                heap.push(Smallest {
+                    key: areas[neighbor],
+                    index: neighbor,
+                });
             }
         }
+    }
+
+    for i in 1..len - 1 {
+        if !removed[i] {
+            significance[i].0 = areas[i];
+        }
+    }
+
+    significance
+}
+
+fn point_significance(
+    points: &[Point],
+    options: &SimplifyOptions,
+    tolerance_sq: f64,
+    with_elevation: bool,
+) -> Vec<(f64, usize)> {
+    match (options.algorithm(), options.max_points()) {
+        (SimplifyAlgorithm::Rdp, _) => stack_rdp(points, tolerance_sq, with_elevation)
+            .into_iter()
+            .map(|significance| (significance, 0))
+            .collect(),
+        (SimplifyAlgorithm::Visvalingam, None) => visvalingam(points, tolerance_sq, with_elevation),
+        // Ranking the points that survive `tolerance_sq` needs the area each would be eliminated at, not the area
+        // still standing when elimination stopped, so keep eliminating until every point has one
+        (SimplifyAlgorithm::Visvalingam, Some(_)) => visvalingam(points, f64::INFINITY, with_elevation),
+    }
+}
+
+// Each point's index, significance and the order Visvalingam eliminated it in, simplifying every partition separately
+// down to `tolerance_sq`
+fn partition_significance(
+    points: &[Point],
+    mapping: &SurfaceMapping,
+    options: &SimplifyOptions,
+    tolerance_sq: f64,
+) -> Vec<(usize, f64, usize)> {
+    let mut partitions = SurfaceGroupIter::new(points, mapping, options.surface_smoothing());
+    if options.keep_road_class_changes() {
+        partitions = partitions.split_road_classes();
+    }
+
+    partitions
+        .flat_map(|points| {
+            // Profile and combined simplification always measure in meters, elevation has no meaning in degrees
+            let significance = match (options.elevation(), options.units()) {
+                (SimplifyElevation::Ignore, SimplifyUnits::Degrees) => {
+                    point_significance(points, options, tolerance_sq, false)
+                }
+                (SimplifyElevation::Ignore, SimplifyUnits::Meters) => {
+                    point_significance(&project_to_meters(points), options, tolerance_sq, false)
+                }
+                (SimplifyElevation::Profile, _) => {
+                    point_significance(&project_to_profile(points), options, tolerance_sq, false)
+                }
+                (SimplifyElevation::Combined, _) => {
+                    point_significance(&project_to_meters(points), options, tolerance_sq, true)
+                }
+            };
+
+            points
+                .iter()
+                .zip(significance)
+                .map(|(point, (significance, order))| (point.index(), significance, order))
+        })
+        .collect()
+}
+
+fn select_points(
+    points: &[Point],
+    significance: &[(usize, f64, usize)],
+    tolerance_sq: f64,
+    max_points: Option<usize>,
+) -> HashSet<usize> {
+    let significant_points = significance
+        .iter()
+        .copied()
+        .filter(|(_, significance, _)| *significance == f64::INFINITY || *significance > tolerance_sq);
+
+    match max_points {
+        None => significant_points.map(|(index, _, _)| index).collect(),
+        Some(max_points) => {
+            let first = points.first().map(|point| point.index());
+            let last = points.last().map(|point| point.index());
+            let is_endpoint = |index: &usize| Some(*index) == first || Some(*index) == last;
+
+            // Track endpoints first, then partition boundaries, then everything else by descending significance,
+            // with the points eliminated last first among equal significance
+            significant_points
+                .sorted_by(
+                    |(a_index, a_significance, a_order), (b_index, b_significance, b_order)| {
+                        is_endpoint(b_index)
+                            .cmp(&is_endpoint(a_index))
+                            .then_with(|| b_significance.total_cmp(a_significance))
+                            .then_with(|| b_order.cmp(a_order))
+                            .then_with(|| a_index.cmp(b_index))
+                    },
+                )
+                .take(max_points)
+                .map(|(index, _, _)| index)
+                .collect()
+        }
+    }
+}
+
+pub fn simplify_points(points: &[Point], mapping: &SurfaceMapping, options: &SimplifyOptions) -> HashSet<usize> {
+    let tolerance_sq = options.tolerance() * options.tolerance();
+    let significance = partition_significance(points, mapping, options, tolerance_sq);
 
-        anchors
+    select_points(points, &significance, tolerance_sq, options.max_points())
+}
+
+// Simplifies points for every tolerance in `tolerances`, in place of the one in `options`, returning the same indexes
+// simplify_points would for each. Significance is computed once and shared between the levels, since both algorithms
+// assign it so that the points kept at a larger tolerance are always a subset of those kept at a smaller one.
+pub fn simplify_points_levels(
+    points: &[Point],
+    mapping: &SurfaceMapping,
+    options: &SimplifyOptions,
+    tolerances: &[f64],
+) -> Vec<HashSet<usize>> {
+    let tolerances_sq = tolerances
+        .iter()
+        .map(|tolerance| tolerance * tolerance)
+        .collect::<Vec<_>>();
+    if tolerances_sq.is_empty() {
+        return Vec::new();
     }
 
-    let tolerance_sq = tolerance * tolerance;
-    SurfaceGroupIter::new(points, mapping)
-        .map(|points| stack_rdp(points, tolerance_sq))
-        .flatten()
+    // RDP has to recurse down to the smallest tolerance to see every split, while Visvalingam has to keep eliminating
+    // points up to the largest one. Either way the significance of every point that matters is exact.
+    let tolerance_sq = match options.algorithm() {
+        SimplifyAlgorithm::Rdp => tolerances_sq.iter().copied().fold(f64::INFINITY, f64::min),
+        SimplifyAlgorithm::Visvalingam => tolerances_sq.iter().copied().fold(0.0, f64::max),
+    };
+    let significance = partition_significance(points, mapping, options, tolerance_sq);
+
+    tolerances_sq
+        .iter()
+        .map(|tolerance_sq| select_points(points, &significance, *tolerance_sq, options.max_points()))
+        .collect()
+}
+
+// Returns every point's index with the tolerance at which it would be removed: simplifying with a smaller tolerance
+// keeps the point, and any tolerance at or above it removes it. Points that are always kept, such as track endpoints
+// and partition boundaries, are removed at infinity. The tolerance in `options` and max_points are ignored.
+pub fn simplify_points_significance(
+    points: &[Point],
+    mapping: &SurfaceMapping,
+    options: &SimplifyOptions,
+) -> Vec<(usize, f64)> {
+    // Run each algorithm to completion, so every point has been split on or eliminated
+    let tolerance_sq = match options.algorithm() {
+        SimplifyAlgorithm::Rdp => 0.0,
+        SimplifyAlgorithm::Visvalingam => f64::INFINITY,
+    };
+
+    partition_significance(points, mapping, options, tolerance_sq)
+        .into_iter()
+        .map(|(index, significance, _)| (index, significance.sqrt()))
         .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::geometry::tests::random_walk;
 
     #[test]
     fn test_surface_group_iterator_all_points_missing_surface_info() {
@@ -89,7 +725,7 @@ mod tests {
             Point::new(2, 0.0, 0.0, 0.0, 0.0, None, None),
         ];
 
-        let groups = SurfaceGroupIter::new(&points, &mapping).collect::<Vec<_>>();
+        let groups = SurfaceGroupIter::new(&points, &mapping, SurfaceSmoothing::None).collect::<Vec<_>>();
 
         assert_eq!(groups, vec![points.as_slice()]);
     }
@@ -107,7 +743,7 @@ mod tests {
             Point::new(2, 0.0, 0.0, 0.0, 0.0, Some(3), None),
         ];
 
-        let groups = SurfaceGroupIter::new(&points, &mapping).collect::<Vec<_>>();
+        let groups = SurfaceGroupIter::new(&points, &mapping, SurfaceSmoothing::None).collect::<Vec<_>>();
 
         assert_eq!(
             groups,
@@ -136,7 +772,7 @@ mod tests {
             Point::new(6, 0.0, 0.0, 0.0, 0.0, None, None),
         ];
 
-        let groups = SurfaceGroupIter::new(&points, &mapping).collect::<Vec<_>>();
+        let groups = SurfaceGroupIter::new(&points, &mapping, SurfaceSmoothing::None).collect::<Vec<_>>();
 
         assert_eq!(
             groups,
@@ -152,14 +788,17 @@ mod tests {
     #[test]
     fn test_simplifying_zero_points() {
         let mapping = SurfaceMapping::new(0);
-        assert_eq!(simplify_points(&[], &mapping, 0.0), HashSet::new());
+        assert_eq!(
+            simplify_points(&[], &mapping, &SimplifyOptions::new(0.0)),
+            HashSet::new()
+        );
     }
 
     #[test]
     fn test_simplifying_one_point() {
         let mapping = SurfaceMapping::new(0);
         assert_eq!(
-            simplify_points(&[Point::default()], &mapping, 0.0),
+            simplify_points(&[Point::default()], &mapping, &SimplifyOptions::new(0.0)),
             HashSet::from_iter([0])
         );
     }
@@ -174,7 +813,7 @@ mod tests {
                     Point::new(1, 1.0, 0.0, 0.0, 0.0, None, None),
                 ],
                 &mapping,
-                0.0
+                &SimplifyOptions::new(0.0)
             ),
             HashSet::from_iter([0, 1])
         );
@@ -191,9 +830,489 @@ mod tests {
                     Point::new(2, 2.0, 2.0, 0.0, 0.0, None, None),
                 ],
                 &mapping,
-                0.0
+                &SimplifyOptions::new(0.0)
             ),
             HashSet::from_iter([0, 1, 2])
         );
     }
+
+    #[test]
+    fn test_visvalingam_removes_smallest_areas() {
+        let mapping = SurfaceMapping::new(0);
+        let mut options = SimplifyOptions::new(0.5);
+        options.set_algorithm(SimplifyAlgorithm::Visvalingam);
+
+        let points = vec![
+            Point::new(0, 0.0, 0.0, 0.0, 0.0, None, None),
+            Point::new(1, 1.0, 0.1, 0.0, 0.0, None, None),
+            Point::new(2, 2.0, 0.0, 0.0, 0.0, None, None),
+            Point::new(3, 3.0, 5.0, 0.0, 0.0, None, None),
+            Point::new(4, 4.0, 0.0, 0.0, 0.0, None, None),
+            Point::new(5, 5.0, 0.0, 0.0, 0.0, None, None),
+        ];
+
+        assert_eq!(
+            simplify_points(&points, &mapping, &options),
+            HashSet::from_iter([0, 2, 3, 4, 5])
+        );
+    }
+
+    #[test]
+    fn test_visvalingam_respects_surface_groups() {
+        let mut mapping = SurfaceMapping::new(99);
+        mapping.add_surface(1, "1".to_string());
+        mapping.add_surface(2, "2".to_string());
+        let mut options = SimplifyOptions::new(1.0);
+        options.set_algorithm(SimplifyAlgorithm::Visvalingam);
+
+        let points = vec![
+            Point::new(0, 0.0, 0.0, 0.0, 0.0, Some(1), None),
+            Point::new(1, 1.0, 0.0, 0.0, 0.0, Some(1), None),
+            Point::new(2, 2.0, 0.0, 0.0, 0.0, Some(1), None),
+            Point::new(3, 3.0, 0.0, 0.0, 0.0, Some(2), None),
+            Point::new(4, 4.0, 0.0, 0.0, 0.0, Some(2), None),
+            Point::new(5, 5.0, 0.0, 0.0, 0.0, Some(2), None),
+        ];
+
+        assert_eq!(
+            simplify_points(&points, &mapping, &options),
+            HashSet::from_iter([0, 2, 3, 5])
+        );
+    }
+
+    #[test]
+    fn test_simplify_algorithm_from_str() {
+        assert_eq!("rdp".parse::<SimplifyAlgorithm>().unwrap(), SimplifyAlgorithm::Rdp);
+        assert_eq!(
+            "visvalingam".parse::<SimplifyAlgorithm>().unwrap(),
+            SimplifyAlgorithm::Visvalingam
+        );
+        assert!("douglas".parse::<SimplifyAlgorithm>().is_err());
+    }
+
+    #[test]
+    fn test_max_points_keeps_most_significant() {
+        let mapping = SurfaceMapping::new(0);
+        let points = vec![
+            Point::new(0, 0.0, 0.0, 0.0, 0.0, None, None),
+            Point::new(1, 1.0, 0.1, 0.0, 0.0, None, None),
+            Point::new(2, 2.0, 0.0, 0.0, 0.0, None, None),
+            Point::new(3, 3.0, 5.0, 0.0, 0.0, None, None),
+            Point::new(4, 4.0, 1.0, 0.0, 0.0, None, None),
+            Point::new(5, 5.0, 0.0, 0.0, 0.0, None, None),
+        ];
+
+        for algorithm in [SimplifyAlgorithm::Rdp, SimplifyAlgorithm::Visvalingam] {
+            let mut options = SimplifyOptions::new(0.0);
+            options.set_algorithm(algorithm);
+
+            options.set_max_points(3);
+            assert_eq!(
+                simplify_points(&points, &mapping, &options),
+                HashSet::from_iter([0, 3, 5])
+            );
+
+            options.set_max_points(2);
+            assert_eq!(simplify_points(&points, &mapping, &options), HashSet::from_iter([0, 5]));
+
+            options.set_max_points(100);
+            assert_eq!(
+                simplify_points(&points, &mapping, &options),
+                HashSet::from_iter([0, 1, 2, 3, 4, 5])
+            );
+        }
+    }
+
+    #[test]
+    fn test_max_points_matches_iterative_visvalingam() {
+        let mapping = SurfaceMapping::new(0);
+        let points = (0..60)
+            .map(|i| {
+                let i = i as f64;
+                Point::new(
+                    i as usize,
+                    i,
+                    (i * 1.7).sin() * 3.0 + (i * 0.31).cos() * 5.0,
+                    0.0,
+                    0.0,
+                    None,
+                    None,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut options = SimplifyOptions::new(0.0);
+        options.set_algorithm(SimplifyAlgorithm::Visvalingam);
+
+        // Repeatedly remove the interior point whose triangle with its current neighbors is smallest
+        let mut remaining = points.clone();
+        while remaining.len() > 2 {
+            options.set_max_points(remaining.len());
+            assert_eq!(
+                simplify_points(&points, &mapping, &options),
+                remaining.iter().map(|point| point.index()).collect::<HashSet<_>>(),
+                "max_points {}",
+                remaining.len()
+            );
+
+            let smallest = (1..remaining.len() - 1)
+                .min_by(|a, b| {
+                    let area = |i: usize| triangle_area_2d(&remaining[i - 1], &remaining[i], &remaining[i + 1]);
+                    area(*a).total_cmp(&area(*b))
+                })
+                .unwrap();
+            remaining.remove(smallest);
+        }
+    }
+
+    #[test]
+    fn test_max_points_keeps_surface_group_boundaries() {
+        let mut mapping = SurfaceMapping::new(99);
+        mapping.add_surface(1, "1".to_string());
+        mapping.add_surface(2, "2".to_string());
+
+        let points = vec![
+            Point::new(0, 0.0, 0.0, 0.0, 0.0, Some(1), None),
+            Point::new(1, 1.0, 3.0, 0.0, 0.0, Some(1), None),
+            Point::new(2, 2.0, 0.0, 0.0, 0.0, Some(1), None),
+            Point::new(3, 3.0, 0.0, 0.0, 0.0, Some(2), None),
+            Point::new(4, 4.0, 0.0, 0.0, 0.0, Some(2), None),
+            Point::new(5, 5.0, 0.0, 0.0, 0.0, Some(2), None),
+        ];
+
+        let mut options = SimplifyOptions::new(0.0);
+        options.set_max_points(4);
+        assert_eq!(
+            simplify_points(&points, &mapping, &options),
+            HashSet::from_iter([0, 2, 3, 5])
+        );
+
+        options.set_max_points(5);
+        assert_eq!(
+            simplify_points(&points, &mapping, &options),
+            HashSet::from_iter([0, 1, 2, 3, 5])
+        );
+    }
+
+    #[test]
+    fn test_tolerance_in_meters_is_independent_of_latitude() {
+        let mapping = SurfaceMapping::new(0);
+        // The middle point is 0.0001 degrees of longitude off a north-south line: ~11m at the equator, ~5.6m at 60N
+        let points_at = |y: f64| {
+            vec![
+                Point::new(0, 10.0, y, 0.0, 0.0, None, None),
+                Point::new(1, 10.0001, y + 0.001, 0.0, 0.0, None, None),
+                Point::new(2, 10.0, y + 0.002, 0.0, 0.0, None, None),
+            ]
+        };
+
+        let mut options = SimplifyOptions::new(8.0);
+        options.set_units(SimplifyUnits::Meters);
+        assert_eq!(
+            simplify_points(&points_at(0.0), &mapping, &options),
+            HashSet::from_iter([0, 1, 2])
+        );
+        assert_eq!(
+            simplify_points(&points_at(60.0), &mapping, &options),
+            HashSet::from_iter([0, 2])
+        );
+
+        let mut options = SimplifyOptions::new(8.0 / 111_195.0);
+        options.set_units(SimplifyUnits::Degrees);
+        assert_eq!(
+            simplify_points(&points_at(0.0), &mapping, &options),
+            simplify_points(&points_at(60.0), &mapping, &options)
+        );
+    }
+
+    #[test]
+    fn test_tolerance_in_meters_across_the_antimeridian() {
+        let mapping = SurfaceMapping::new(0);
+        let points = vec![
+            Point::new(0, 179.999, -17.0, 0.0, 0.0, None, None),
+            Point::new(1, 179.9995, -17.0, 0.0, 0.0, None, None),
+            Point::new(2, -179.9995, -17.0, 0.0, 0.0, None, None),
+            Point::new(3, -179.999, -17.0, 0.0, 0.0, None, None),
+        ];
+
+        let mut options = SimplifyOptions::new(1.0);
+        options.set_units(SimplifyUnits::Meters);
+        assert_eq!(simplify_points(&points, &mapping, &options), HashSet::from_iter([0, 3]));
+    }
+
+    #[test]
+    fn test_elevation_keeps_summit_on_straight_road() {
+        let mapping = SurfaceMapping::new(0);
+        // A straight road along the equator, 0.001 degrees (~111m) between points, climbing 40m to a summit
+        let points = vec![
+            Point::new(0, 0.0, 0.0, 0.0, 100.0, None, None),
+            Point::new(1, 0.001, 0.0, 111.2, 120.0, None, None),
+            Point::new(2, 0.002, 0.0, 222.4, 140.0, None, None),
+            Point::new(3, 0.003, 0.0, 333.6, 120.0, None, None),
+            Point::new(4, 0.004, 0.0, 444.8, 100.0, None, None),
+        ];
+
+        for algorithm in [SimplifyAlgorithm::Rdp, SimplifyAlgorithm::Visvalingam] {
+            let mut options = SimplifyOptions::new(10.0);
+            options.set_algorithm(algorithm);
+            options.set_units(SimplifyUnits::Meters);
+            assert_eq!(simplify_points(&points, &mapping, &options), HashSet::from_iter([0, 4]));
+
+            options.set_elevation(SimplifyElevation::Profile);
+            assert_eq!(
+                simplify_points(&points, &mapping, &options),
+                HashSet::from_iter([0, 2, 4])
+            );
+
+            options.set_elevation(SimplifyElevation::Combined);
+            assert_eq!(
+                simplify_points(&points, &mapping, &options),
+                HashSet::from_iter([0, 2, 4])
+            );
+        }
+    }
+
+    #[test]
+    fn test_combined_elevation_keeps_horizontal_turns() {
+        let mapping = SurfaceMapping::new(0);
+        // A flat road with a ~111m detour to the north, which a profile alone can't see
+        let points = vec![
+            Point::new(0, 0.0, 0.0, 0.0, 100.0, None, None),
+            Point::new(1, 0.001, 0.001, 157.3, 100.0, None, None),
+            Point::new(2, 0.002, 0.0, 314.6, 100.0, None, None),
+        ];
+
+        let mut options = SimplifyOptions::new(10.0);
+        options.set_elevation(SimplifyElevation::Profile);
+        assert_eq!(simplify_points(&points, &mapping, &options), HashSet::from_iter([0, 2]));
+
+        options.set_elevation(SimplifyElevation::Combined);
+        assert_eq!(
+            simplify_points(&points, &mapping, &options),
+            HashSet::from_iter([0, 1, 2])
+        );
+    }
+
+    fn blip_track() -> (SurfaceMapping, Vec<Point>) {
+        let mut mapping = SurfaceMapping::new(99);
+        mapping.add_surface(1, "Paved".to_string());
+        mapping.add_surface(20, "Gravel".to_string());
+
+        // A straight paved road with single point Gravel blips, and a real gravel stretch at the end
+        let surfaces = [1, 1, 20, 1, 1, 1, 20, 1, 20, 20, 20, 20];
+        let points = surfaces
+            .iter()
+            .enumerate()
+            .map(|(i, s)| Point::new(i, i as f64 * 0.001, 0.0, i as f64 * 100.0, 0.0, Some(*s), None))
+            .collect();
+
+        (mapping, points)
+    }
+
+    fn run_bounds<'a>(runs: &[GroupRun<'a>]) -> Vec<(usize, usize, Option<&'a str>)> {
+        runs.iter()
+            .map(|run| (run.start, run.end, run.group.map(String::as_str)))
+            .collect()
+    }
+
+    #[test]
+    fn test_surface_smoothing() {
+        let (mapping, points) = blip_track();
+
+        assert_eq!(
+            run_bounds(&surface_group_runs(&points, &mapping, SurfaceSmoothing::None)),
+            vec![
+                (0, 2, Some("Paved")),
+                (2, 3, Some("Gravel")),
+                (3, 6, Some("Paved")),
+                (6, 7, Some("Gravel")),
+                (7, 8, Some("Paved")),
+                (8, 12, Some("Gravel")),
+            ]
+        );
+
+        let smoothed = vec![(0, 8, Some("Paved")), (8, 12, Some("Gravel"))];
+        assert_eq!(
+            run_bounds(&surface_group_runs(&points, &mapping, SurfaceSmoothing::MinPoints(2))),
+            smoothed
+        );
+        assert_eq!(
+            run_bounds(&surface_group_runs(
+                &points,
+                &mapping,
+                SurfaceSmoothing::MinMeters(150.0)
+            )),
+            smoothed
+        );
+        // The paved point at index 7 sits between gravel points, so a majority filter moves it to the gravel stretch
+        assert_eq!(
+            run_bounds(&surface_group_runs(&points, &mapping, SurfaceSmoothing::Majority(3))),
+            vec![(0, 7, Some("Paved")), (7, 12, Some("Gravel"))]
+        );
+        assert_eq!(
+            run_bounds(&surface_group_runs(&points, &mapping, SurfaceSmoothing::Majority(9))),
+            vec![(0, 6, Some("Paved")), (6, 12, Some("Gravel"))]
+        );
+
+        assert_eq!(
+            surface_group_runs(&[], &mapping, SurfaceSmoothing::MinMeters(150.0)),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_surface_smoothing_removes_blip_anchors() {
+        let (mapping, points) = blip_track();
+
+        let mut options = SimplifyOptions::new(0.0001);
+        assert_eq!(
+            simplify_points(&points, &mapping, &options),
+            HashSet::from_iter([0, 1, 2, 3, 5, 6, 7, 8, 11])
+        );
+
+        options.set_surface_smoothing(SurfaceSmoothing::MinPoints(2));
+        assert_eq!(
+            simplify_points(&points, &mapping, &options),
+            HashSet::from_iter([0, 7, 8, 11])
+        );
+    }
+
+    #[test]
+    fn test_surface_smoothing_from_mode() {
+        assert_eq!(
+            SurfaceSmoothing::from_mode("none", None).unwrap(),
+            SurfaceSmoothing::None
+        );
+        assert_eq!(
+            SurfaceSmoothing::from_mode("meters", Some(25.5)).unwrap(),
+            SurfaceSmoothing::MinMeters(25.5)
+        );
+        assert_eq!(
+            SurfaceSmoothing::from_mode("points", Some(3.0)).unwrap(),
+            SurfaceSmoothing::MinPoints(3)
+        );
+        assert_eq!(
+            SurfaceSmoothing::from_mode("majority", Some(5.0)).unwrap(),
+            SurfaceSmoothing::Majority(5)
+        );
+
+        assert!(SurfaceSmoothing::from_mode("meters", None).is_err());
+        assert!(SurfaceSmoothing::from_mode("meters", Some(-1.0)).is_err());
+        assert!(SurfaceSmoothing::from_mode("points", Some(1.5)).is_err());
+        assert!(SurfaceSmoothing::from_mode("points", Some(0.0)).is_err());
+        assert!(SurfaceSmoothing::from_mode("majority", Some(4.0)).is_err());
+        assert!(SurfaceSmoothing::from_mode("median", Some(3.0)).is_err());
+    }
+
+    #[test]
+    fn test_surface_group_iterator_split_road_classes() {
+        let mut mapping = SurfaceMapping::new(99);
+        mapping.add_surface(1, "1".to_string());
+        mapping.add_surface(2, "2".to_string());
+
+        let points = vec![
+            Point::new(0, 0.0, 0.0, 0.0, 0.0, Some(1), Some(5)),
+            Point::new(1, 0.0, 0.0, 0.0, 0.0, Some(1), Some(5)),
+            Point::new(2, 0.0, 0.0, 0.0, 0.0, Some(1), Some(6)),
+            Point::new(3, 0.0, 0.0, 0.0, 0.0, Some(2), Some(6)),
+            Point::new(4, 0.0, 0.0, 0.0, 0.0, Some(2), None),
+        ];
+
+        let groups = SurfaceGroupIter::new(&points, &mapping, SurfaceSmoothing::None)
+            .split_road_classes()
+            .collect::<Vec<_>>();
+
+        assert_eq!(groups, vec![&points[0..2], &points[2..3], &points[3..4], &points[4..5]]);
+    }
+
+    #[test]
+    fn test_keep_road_class_changes() {
+        // A straight line with no surface information, changing road class twice
+        let mapping = SurfaceMapping::new(99);
+        let road_classes = [1, 1, 1, 2, 2, 2, 1];
+        let points = road_classes
+            .iter()
+            .enumerate()
+            .map(|(i, r)| Point::new(i, i as f64 * 0.001, 0.0, 0.0, 0.0, None, Some(*r)))
+            .collect::<Vec<_>>();
+
+        let mut options = SimplifyOptions::new(0.0001);
+        assert_eq!(simplify_points(&points, &mapping, &options), HashSet::from_iter([0, 6]));
+
+        options.set_keep_road_class_changes(true);
+        assert_eq!(
+            simplify_points(&points, &mapping, &options),
+            HashSet::from_iter([0, 2, 3, 5, 6])
+        );
+
+        options.set_max_points(3);
+        assert_eq!(
+            simplify_points(&points, &mapping, &options),
+            HashSet::from_iter([0, 2, 6])
+        );
+    }
+
+    #[test]
+    fn test_simplify_points_levels_match_simplify_points() {
+        let mut mapping = SurfaceMapping::new(99);
+        mapping.add_surface(1, "Paved".to_string());
+        mapping.add_surface(2, "Gravel".to_string());
+
+        let points = random_walk(12345, 500);
+
+        let tolerances = [0.001, 0.0, 0.00001, 0.0001, 0.0005];
+        for algorithm in [SimplifyAlgorithm::Rdp, SimplifyAlgorithm::Visvalingam] {
+            for max_points in [None, Some(20)] {
+                let mut options = SimplifyOptions::new(0.0);
+                options.set_algorithm(algorithm);
+                if let Some(max_points) = max_points {
+                    options.set_max_points(max_points);
+                }
+
+                let levels = simplify_points_levels(&points, &mapping, &options, &tolerances);
+                assert_eq!(levels.len(), tolerances.len());
+
+                for (tolerance, level) in tolerances.iter().zip(&levels) {
+                    let mut single_options = options.clone();
+                    single_options.tolerance = *tolerance;
+                    assert_eq!(*level, simplify_points(&points, &mapping, &single_options));
+                }
+            }
+        }
+
+        assert!(simplify_points_levels(&points, &mapping, &SimplifyOptions::new(0.0), &[]).is_empty());
+    }
+
+    #[test]
+    fn test_simplify_points_significance_matches_simplify_points() {
+        let mut mapping = SurfaceMapping::new(99);
+        mapping.add_surface(1, "Paved".to_string());
+        mapping.add_surface(2, "Gravel".to_string());
+
+        let points = random_walk(54321, 300);
+
+        for algorithm in [SimplifyAlgorithm::Rdp, SimplifyAlgorithm::Visvalingam] {
+            let mut options = SimplifyOptions::new(0.0);
+            options.set_algorithm(algorithm);
+
+            let significance = simplify_points_significance(&points, &mapping, &options);
+            assert_eq!(
+                significance.iter().map(|(index, _)| *index).collect::<Vec<_>>(),
+                (0..300).collect::<Vec<_>>()
+            );
+            assert_eq!(significance[0].1, f64::INFINITY);
+            assert_eq!(significance[75].1, f64::INFINITY);
+            assert_eq!(significance[299].1, f64::INFINITY);
+
+            for tolerance in [0.0, 0.00001, 0.0001, 0.0005, 0.001] {
+                options.tolerance = tolerance;
+                let kept = significance
+                    .iter()
+                    .filter(|(_, removed_at)| tolerance < *removed_at)
+                    .map(|(index, _)| *index)
+                    .collect::<HashSet<_>>();
+                assert_eq!(kept, simplify_points(&points, &mapping, &options));
+            }
+        }
+    }
 }
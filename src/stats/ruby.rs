@@ -0,0 +1,198 @@
+use super::rust::{elevation_stats, surface_segments, surface_summary, SurfaceSegment};
+use crate::error::ruby::raise;
+use crate::error::rust::TracklibRwgpsError;
+use crate::geometry::{section_to_points, with_section_points, IrrelevantPointsBehavior};
+use crate::ruby::{f64_from_ruby, key_material_from_ruby};
+use crate::schema::ruby::RubyPointSchema;
+use crate::schema::rust::PointSchema;
+use crate::surface::ruby::RubySurfaceMapping;
+use itertools::process_results;
+use rutie::{methods, module, AnyObject, Array, Float, Hash, Integer, NilClass, Object, RString, VM};
+
+const DEFAULT_GRADE_DISTANCE: f64 = 100.0;
+
+module!(TracklibRwgps);
+
+methods!(
+    TracklibRwgps,
+    _rtself,
+    fn stats_section_elevation_stats(
+        track_reader: ruby_tracklib::read::TrackReader,
+        index: Integer,
+        hysteresis: AnyObject,
+        grade_distance: AnyObject,
+        key_material: AnyObject,
+        point_schema: RubyPointSchema) -> AnyObject {
+        let ruby_track_reader = track_reader.map_err(VM::raise_ex).unwrap();
+
+        let ruby_index = index.map_err(VM::raise_ex).unwrap();
+        let rust_index = usize::try_from(ruby_index.to_u64())
+            .map_err(|_| raise(TracklibRwgpsError::SectionMissing))
+            .unwrap();
+
+        let rust_hysteresis = f64_from_ruby(hysteresis.map_err(VM::raise_ex).unwrap());
+        let rust_grade_distance = grade_distance
+            .ok()
+            .filter(|distance| !distance.is_nil())
+            .map_or(DEFAULT_GRADE_DISTANCE, f64_from_ruby);
+
+        let ruby_key_material = key_material.ok().and_then(key_material_from_ruby);
+        let rust_key_material = ruby_key_material.as_ref().map(|key| key.to_bytes_unchecked());
+
+        let rust_point_schema = point_schema
+            .ok()
+            .map_or_else(PointSchema::new, |ruby_point_schema| ruby_point_schema.inner().clone());
+
+        ruby_track_reader.with_track_reader(|track_reader| {
+            let mut section = track_reader
+                .section(rust_index)
+                .ok_or(TracklibRwgpsError::SectionMissing)
+                .map_err(raise)
+                .unwrap();
+            let stats = with_section_points(
+                &mut section,
+                rust_key_material,
+                &rust_point_schema,
+                None,
+                IrrelevantPointsBehavior::Ignore,
+                |points| {
+                    process_results(points, |points| elevation_stats(points, rust_hysteresis, rust_grade_distance))?
+                },
+            )
+            .map_err(raise)
+            .unwrap();
+
+            match stats {
+                Some(stats) => {
+                    let mut hash = Hash::new();
+                    hash.store(RString::from("ascent"), Float::new(stats.ascent()));
+                    hash.store(RString::from("descent"), Float::new(stats.descent()));
+                    hash.store(RString::from("min_elevation"), Float::new(stats.min_elevation()));
+                    hash.store(RString::from("max_elevation"), Float::new(stats.max_elevation()));
+                    match stats.max_grade() {
+                        Some(max_grade) => hash.store(RString::from("max_grade"), Float::new(max_grade)),
+                        None => hash.store(RString::from("max_grade"), NilClass::new()),
+                    };
+                    hash.to_any_object()
+                }
+                None => NilClass::new().to_any_object(),
+            }
+        })
+    },
+    fn stats_section_surface_summary(
+        track_reader: ruby_tracklib::read::TrackReader,
+        index: Integer,
+        mapping: RubySurfaceMapping,
+        key_material: AnyObject,
+        point_schema: RubyPointSchema) -> Hash {
+        let ruby_track_reader = track_reader.map_err(VM::raise_ex).unwrap();
+
+        let ruby_index = index.map_err(VM::raise_ex).unwrap();
+        let rust_index = usize::try_from(ruby_index.to_u64())
+            .map_err(|_| raise(TracklibRwgpsError::SectionMissing))
+            .unwrap();
+
+        let ruby_mapping = mapping.map_err(VM::raise_ex).unwrap();
+        let rust_mapping = ruby_mapping.inner();
+
+        let ruby_key_material = key_material.ok().and_then(key_material_from_ruby);
+        let rust_key_material = ruby_key_material.as_ref().map(|key| key.to_bytes_unchecked());
+
+        let rust_point_schema = point_schema
+            .ok()
+            .map_or_else(PointSchema::new, |ruby_point_schema| ruby_point_schema.inner().clone());
+
+        ruby_track_reader.with_track_reader(|track_reader| {
+            let mut section = track_reader
+                .section(rust_index)
+                .ok_or(TracklibRwgpsError::SectionMissing)
+                .map_err(raise)
+                .unwrap();
+            let summary = with_section_points(
+                &mut section,
+                rust_key_material,
+                &rust_point_schema,
+                None,
+                IrrelevantPointsBehavior::Ignore,
+                |points| process_results(points, |points| surface_summary(points, rust_mapping)),
+            )
+            .map_err(raise)
+            .unwrap();
+
+            // Groups are kept apart from the unknown meters, so a group may be named "unknown" too
+            let mut groups = Hash::new();
+            for (group, meters) in summary.groups() {
+                groups.store(RString::from(group.clone()), Float::new(*meters));
+            }
+
+            let mut hash = Hash::new();
+            hash.store(RString::from("groups"), groups);
+            hash.store(RString::from("unknown"), Float::new(summary.unknown()));
+            hash
+        })
+    },
+    fn stats_section_surface_segments(
+        track_reader: ruby_tracklib::read::TrackReader,
+        index: Integer,
+        mapping: RubySurfaceMapping,
+        min_length: AnyObject,
+        key_material: AnyObject,
+        point_schema: RubyPointSchema) -> Array {
+        let ruby_track_reader = track_reader.map_err(VM::raise_ex).unwrap();
+
+        let ruby_index = index.map_err(VM::raise_ex).unwrap();
+        let rust_index = usize::try_from(ruby_index.to_u64())
+            .map_err(|_| raise(TracklibRwgpsError::SectionMissing))
+            .unwrap();
+
+        let ruby_mapping = mapping.map_err(VM::raise_ex).unwrap();
+        let rust_mapping = ruby_mapping.inner();
+
+        let rust_min_length = min_length
+            .ok()
+            .filter(|length| !length.is_nil())
+            .map_or(0.0, f64_from_ruby);
+
+        let ruby_key_material = key_material.ok().and_then(key_material_from_ruby);
+        let rust_key_material = ruby_key_material.as_ref().map(|key| key.to_bytes_unchecked());
+
+        let rust_point_schema = point_schema
+            .ok()
+            .map_or_else(PointSchema::new, |ruby_point_schema| ruby_point_schema.inner().clone());
+
+        ruby_track_reader.with_track_reader(|track_reader| {
+            let mut section = track_reader
+                .section(rust_index)
+                .ok_or(TracklibRwgpsError::SectionMissing)
+                .map_err(raise)
+                .unwrap();
+            let points = section_to_points(
+                &mut section,
+                rust_key_material,
+                &rust_point_schema,
+                None,
+                IrrelevantPointsBehavior::Ignore,
+            )
+            .map_err(raise)
+            .unwrap();
+
+            surface_segments(&points, rust_mapping, rust_min_length)
+                .iter()
+                .map(segment_to_hash)
+                .collect()
+        })
+    }
+);
+
+fn segment_to_hash(segment: &SurfaceSegment) -> Hash {
+    let mut hash = Hash::new();
+    hash.store(RString::from("start_index"), Integer::from(segment.start_index() as u64));
+    hash.store(RString::from("end_index"), Integer::from(segment.end_index() as u64));
+    hash.store(RString::from("start_d"), Float::new(segment.start_d()));
+    hash.store(RString::from("end_d"), Float::new(segment.end_d()));
+    match segment.group() {
+        Some(group) => hash.store(RString::from("group"), RString::from(group.clone())),
+        None => hash.store(RString::from("group"), NilClass::new()),
+    };
+    hash
+}
@@ -0,0 +1,433 @@
+use crate::error::rust::TracklibRwgpsError;
+use crate::geometry::Point;
+use crate::simplify::rust::{merge_short_runs, run_distance, surface_group_runs, SurfaceSmoothing};
+use crate::surface::rust::SurfaceMapping;
+use std::borrow::Borrow;
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ElevationStats {
+    ascent: f64,
+    descent: f64,
+    min_elevation: f64,
+    max_elevation: f64,
+    max_grade: Option<f64>,
+}
+
+impl ElevationStats {
+    pub fn ascent(&self) -> f64 {
+        self.ascent
+    }
+
+    pub fn descent(&self) -> f64 {
+        self.descent
+    }
+
+    pub fn min_elevation(&self) -> f64 {
+        self.min_elevation
+    }
+
+    pub fn max_elevation(&self) -> f64 {
+        self.max_elevation
+    }
+
+    // Steepest climb in percent over any stretch of at least `grade_distance` meters, None if the track is shorter
+    pub fn max_grade(&self) -> Option<f64> {
+        self.max_grade
+    }
+}
+
+// Tracks the steepest climb while points stream past. Starts are held until a point at least `grade_distance` meters
+// further along turns up, so only the last `grade_distance` meters of points are ever kept.
+struct MaxGrade {
+    grade_distance: f64,
+    pending: VecDeque<(f64, f64)>,
+    max_grade: Option<f64>,
+}
+
+impl MaxGrade {
+    fn new(grade_distance: f64) -> Self {
+        Self {
+            grade_distance,
+            pending: VecDeque::new(),
+            max_grade: None,
+        }
+    }
+
+    fn push(&mut self, point: &Point) {
+        // d only ever grows along a track, so once the oldest start is too close every later one is too
+        while let Some(&(start_d, start_e)) = self.pending.front() {
+            if point.d() - start_d < self.grade_distance {
+                break;
+            }
+            self.pending.pop_front();
+
+            let distance = point.d() - start_d;
+            if distance > 0.0 {
+                let grade = (point.e() - start_e) / distance * 100.0;
+                self.max_grade = Some(self.max_grade.map_or(grade, |max_grade| max_grade.max(grade)));
+            }
+        }
+
+        self.pending.push_back((point.d(), point.e()));
+    }
+}
+
+// Elevation only counts towards ascent or descent once it has moved at least `hysteresis` meters away from the last
+// counted elevation, so GPS noise smaller than that doesn't add up. `hysteresis` must be finite and not negative, and
+// `grade_distance` finite and positive. Returns None when there are no points.
+pub fn elevation_stats<I>(
+    points: I,
+    hysteresis: f64,
+    grade_distance: f64,
+) -> Result<Option<ElevationStats>, TracklibRwgpsError>
+where
+    I: IntoIterator,
+    I::Item: Borrow<Point>,
+{
+    if !(hysteresis.is_finite() && hysteresis >= 0.0) {
+        return Err(TracklibRwgpsError::InvalidStatsOption(format!(
+            "Elevation stats hysteresis {hysteresis} is not valid, it must be finite and at least 0"
+        )));
+    }
+    if !(grade_distance.is_finite() && grade_distance > 0.0) {
+        return Err(TracklibRwgpsError::InvalidStatsOption(format!(
+            "Elevation stats grade_distance {grade_distance} is not valid, it must be finite and greater than 0"
+        )));
+    }
+
+    let mut points = points.into_iter().peekable();
+    let Some(first_point) = points.peek() else {
+        return Ok(None);
+    };
+    let first_e = first_point.borrow().e();
+
+    let mut stats = ElevationStats {
+        ascent: 0.0,
+        descent: 0.0,
+        min_elevation: first_e,
+        max_elevation: first_e,
+        max_grade: None,
+    };
+    let mut max_grade = MaxGrade::new(grade_distance);
+    let mut reference = first_e;
+
+    for point in points {
+        let point = point.borrow();
+        max_grade.push(point);
+
+        let e = point.e();
+        stats.min_elevation = stats.min_elevation.min(e);
+        stats.max_elevation = stats.max_elevation.max(e);
+
+        if e - reference >= hysteresis {
+            stats.ascent += e - reference;
+            reference = e;
+        } else if reference - e >= hysteresis {
+            stats.descent += reference - e;
+            reference = e;
+        }
+    }
+
+    stats.max_grade = max_grade.max_grade;
+    Ok(Some(stats))
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SurfaceSummary {
+    groups: HashMap<String, f64>,
+    unknown: f64,
+}
+
+impl SurfaceSummary {
+    // Meters per surface group name
+    pub fn groups(&self) -> &HashMap<String, f64> {
+        &self.groups
+    }
+
+    // Meters on points without a surface group
+    pub fn unknown(&self) -> f64 {
+        self.unknown
+    }
+}
+
+// The distance between two points counts towards the surface group of the first of them
+pub fn surface_summary<I>(points: I, mapping: &SurfaceMapping) -> SurfaceSummary
+where
+    I: IntoIterator,
+    I::Item: Borrow<Point>,
+{
+    let mut summary = SurfaceSummary::default();
+    let mut prev: Option<(f64, Option<&String>)> = None;
+
+    for point in points {
+        let point = point.borrow();
+        if let Some((prev_d, prev_group)) = prev {
+            let distance = point.d() - prev_d;
+            match prev_group {
+                Some(group) => *summary.groups.entry(group.clone()).or_insert(0.0) += distance,
+                None => summary.unknown += distance,
+            }
+        }
+        prev = Some((point.d(), mapping.get_surface_group(point)));
+    }
+
+    summary
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SurfaceSegment {
+    start_index: usize,
+    end_index: usize,
+    start_d: f64,
+    end_d: f64,
+    group: Option<String>,
+}
+
+impl SurfaceSegment {
+    pub fn start_index(&self) -> usize {
+        self.start_index
+    }
+
+    pub fn end_index(&self) -> usize {
+        self.end_index
+    }
+
+    pub fn start_d(&self) -> f64 {
+        self.start_d
+    }
+
+    pub fn end_d(&self) -> f64 {
+        self.end_d
+    }
+
+    pub fn group(&self) -> Option<&String> {
+        self.group.as_ref()
+    }
+
+    pub fn length(&self) -> f64 {
+        self.end_d - self.start_d
+    }
+}
+
+// Splits points into contiguous runs of the same surface group. Each segment ends on the first point of the next one,
+// so the segments cover the whole distance without gaps. Segments shorter than `min_length` meters are merged into
+// their longer neighbour, shortest first.
+pub fn surface_segments(points: &[Point], mapping: &SurfaceMapping, min_length: f64) -> Vec<SurfaceSegment> {
+    let mut runs = surface_group_runs(points, mapping, SurfaceSmoothing::None);
+    merge_short_runs(&mut runs, min_length, |run| run_distance(points, run));
+
+    runs.into_iter()
+        .filter_map(|run| {
+            let end = run.end.min(points.len() - 1);
+            (end > run.start).then(|| SurfaceSegment {
+                start_index: points[run.start].index(),
+                end_index: points[end].index(),
+                start_d: points[run.start].d(),
+                end_d: points[end].d(),
+                group: run.group.cloned(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(elevations: &[f64]) -> Vec<Point> {
+        elevations
+            .iter()
+            .enumerate()
+            .map(|(i, e)| Point::new(i, 0.0, 0.0, i as f64 * 100.0, *e, None, None))
+            .collect()
+    }
+
+    #[test]
+    fn test_elevation_stats_empty() {
+        assert_eq!(elevation_stats(&[], 0.0, 100.0).unwrap(), None);
+    }
+
+    #[test]
+    fn test_elevation_stats_hysteresis() {
+        let points = profile(&[100.0, 101.0, 100.0, 101.0, 110.0, 108.0, 120.0, 90.0]);
+
+        let stats = elevation_stats(&points, 0.0, 100.0).unwrap().unwrap();
+        assert_eq!(stats.ascent(), 1.0 + 1.0 + 9.0 + 12.0);
+        assert_eq!(stats.descent(), 1.0 + 2.0 + 30.0);
+        assert_eq!(stats.min_elevation(), 90.0);
+        assert_eq!(stats.max_elevation(), 120.0);
+
+        // Wobbles smaller than 5m are ignored
+        let stats = elevation_stats(&points, 5.0, 100.0).unwrap().unwrap();
+        assert_eq!(stats.ascent(), 20.0);
+        assert_eq!(stats.descent(), 30.0);
+    }
+
+    #[test]
+    fn test_elevation_stats_max_grade() {
+        let points = profile(&[100.0, 101.0, 100.0, 101.0, 110.0, 108.0, 120.0]);
+
+        assert_eq!(
+            elevation_stats(&points, 0.0, 100.0).unwrap().unwrap().max_grade(),
+            Some(12.0)
+        );
+        assert_eq!(
+            elevation_stats(&points, 0.0, 200.0).unwrap().unwrap().max_grade(),
+            Some(5.0)
+        );
+        assert_eq!(
+            elevation_stats(&points, 0.0, 1000.0).unwrap().unwrap().max_grade(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_elevation_stats_invalid_options() {
+        let points = profile(&[100.0, 101.0, 100.0]);
+
+        for (hysteresis, grade_distance) in [
+            (-1.0, 100.0),
+            (f64::NAN, 100.0),
+            (f64::INFINITY, 100.0),
+            (0.0, 0.0),
+            (0.0, -100.0),
+            (0.0, f64::NAN),
+            (0.0, f64::INFINITY),
+        ] {
+            assert!(matches!(
+                elevation_stats(&points, hysteresis, grade_distance),
+                Err(TracklibRwgpsError::InvalidStatsOption(_))
+            ));
+            assert!(matches!(
+                elevation_stats(&[], hysteresis, grade_distance),
+                Err(TracklibRwgpsError::InvalidStatsOption(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_elevation_stats_from_owned_points() {
+        // Unevenly spaced points, so grade stretches end at varying distances past `grade_distance`
+        let points = [0.0, 30.0, 35.0, 90.0, 160.0, 170.0, 260.0, 400.0, 410.0]
+            .iter()
+            .zip([100.0, 103.0, 102.0, 110.0, 104.0, 111.0, 125.0, 119.0, 121.0])
+            .enumerate()
+            .map(|(i, (d, e))| Point::new(i, 0.0, 0.0, *d, e, None, None))
+            .collect::<Vec<_>>();
+
+        for grade_distance in [1.0, 50.0, 100.0, 150.0, 500.0] {
+            // The steepest stretch from each point to the first one at least `grade_distance` further along
+            let expected = (0..points.len())
+                .filter_map(|start| {
+                    let end = points[start + 1..]
+                        .iter()
+                        .find(|point| point.d() - points[start].d() >= grade_distance)?;
+                    Some((end.e() - points[start].e()) / (end.d() - points[start].d()) * 100.0)
+                })
+                .reduce(f64::max);
+
+            let stats = elevation_stats(points.clone(), 2.0, grade_distance).unwrap().unwrap();
+            assert_eq!(stats.max_grade(), expected);
+            assert_eq!(Some(stats), elevation_stats(&points, 2.0, grade_distance).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_surface_summary() {
+        let mut mapping = SurfaceMapping::new(99);
+        mapping.add_surface(1, "Paved".to_string());
+        mapping.add_surface(2, "Paved".to_string());
+        mapping.add_surface(20, "Gravel".to_string());
+
+        let points = vec![
+            Point::new(0, 0.0, 0.0, 0.0, 0.0, Some(1), None),
+            Point::new(1, 0.0, 0.0, 100.0, 0.0, Some(2), None),
+            Point::new(2, 0.0, 0.0, 250.0, 0.0, Some(20), None),
+            Point::new(3, 0.0, 0.0, 300.0, 0.0, None, None),
+            Point::new(4, 0.0, 0.0, 325.0, 0.0, Some(1), None),
+            Point::new(5, 0.0, 0.0, 400.0, 0.0, Some(20), None),
+        ];
+
+        let summary = surface_summary(&points, &mapping);
+        assert_eq!(
+            summary.groups(),
+            &HashMap::from([("Paved".to_string(), 325.0), ("Gravel".to_string(), 50.0)])
+        );
+        assert_eq!(summary.unknown(), 25.0);
+
+        assert_eq!(surface_summary(points.iter().cloned(), &mapping), summary);
+        assert_eq!(surface_summary(&points[..1], &mapping), SurfaceSummary::default());
+    }
+
+    fn surface_segment_points() -> (SurfaceMapping, Vec<Point>) {
+        let mut mapping = SurfaceMapping::new(99);
+        mapping.add_surface(1, "Paved".to_string());
+        mapping.add_surface(20, "Gravel".to_string());
+
+        let points = vec![
+            Point::new(0, 0.0, 0.0, 0.0, 0.0, Some(1), None),
+            Point::new(1, 0.0, 0.0, 100.0, 0.0, Some(1), None),
+            Point::new(2, 0.0, 0.0, 200.0, 0.0, Some(20), None),
+            Point::new(3, 0.0, 0.0, 210.0, 0.0, Some(1), None),
+            Point::new(4, 0.0, 0.0, 300.0, 0.0, Some(1), None),
+            Point::new(5, 0.0, 0.0, 330.0, 0.0, None, None),
+            Point::new(6, 0.0, 0.0, 340.0, 0.0, Some(20), None),
+            Point::new(7, 0.0, 0.0, 700.0, 0.0, Some(20), None),
+            Point::new(8, 0.0, 0.0, 710.0, 0.0, Some(1), None),
+        ];
+
+        (mapping, points)
+    }
+
+    fn segment_ranges(segments: &[SurfaceSegment]) -> Vec<(usize, usize, f64, f64, Option<&str>)> {
+        segments
+            .iter()
+            .map(|segment| {
+                (
+                    segment.start_index(),
+                    segment.end_index(),
+                    segment.start_d(),
+                    segment.end_d(),
+                    segment.group().map(String::as_str),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_surface_segments() {
+        let (mapping, points) = surface_segment_points();
+
+        assert_eq!(
+            segment_ranges(&surface_segments(&points, &mapping, 0.0)),
+            vec![
+                (0, 2, 0.0, 200.0, Some("Paved")),
+                (2, 3, 200.0, 210.0, Some("Gravel")),
+                (3, 5, 210.0, 330.0, Some("Paved")),
+                (5, 6, 330.0, 340.0, None),
+                (6, 8, 340.0, 710.0, Some("Gravel")),
+            ]
+        );
+
+        assert_eq!(surface_segments(&points[..1], &mapping, 0.0), vec![]);
+        assert_eq!(surface_segments(&[], &mapping, 0.0), vec![]);
+    }
+
+    #[test]
+    fn test_surface_segments_merge_short_runs() {
+        let (mapping, points) = surface_segment_points();
+
+        // The short gravel run joins the paved runs around it, the unknown run joins the longer gravel run after it
+        assert_eq!(
+            segment_ranges(&surface_segments(&points, &mapping, 50.0)),
+            vec![(0, 5, 0.0, 330.0, Some("Paved")), (5, 8, 330.0, 710.0, Some("Gravel"))]
+        );
+
+        // Everything merges into one segment, never less
+        assert_eq!(
+            segment_ranges(&surface_segments(&points, &mapping, 1000.0)),
+            vec![(0, 8, 0.0, 710.0, Some("Gravel"))]
+        );
+    }
+}
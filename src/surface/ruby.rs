@@ -1,6 +1,8 @@
 use super::rust::{RoadClassMapping, SurfaceMapping};
+use crate::error::ruby::raise;
+use crate::error::rust::TracklibRwgpsError;
 use rutie::{
-    class, methods, wrappable_struct, AnyObject, Array, Class, Float, Integer, Module, NilClass, Object, RString,
+    class, methods, wrappable_struct, AnyObject, Array, Float, Integer, Module, NilClass, Object, RString,
     VerifiedObject, VM,
 };
 
@@ -31,17 +33,23 @@ methods!(
             })
             .collect();
         if bbox_vec.len() != 4 {
-            VM::raise(Class::from_existing("Exception"), "BBOX Array len must be 4");
+            raise(TracklibRwgpsError::InvalidBbox("BBOX Array len must be 4".to_string()));
         }
         let bbox = [bbox_vec[0], bbox_vec[1], bbox_vec[2], bbox_vec[3]];
 
-        let inner = RoadClassInner {
-            inner: crate::surface::rust::RoadClassMapping::new(bbox),
-        };
+        wrap_road_class_mapping(RoadClassMapping::try_new(bbox).map_err(raise).unwrap())
+    },
+    fn road_class_mapping_from_geojson(geojson: RString) -> AnyObject {
+        let ruby_geojson = geojson.map_err(|e| VM::raise_ex(e)).unwrap();
+        let mapping = RoadClassMapping::from_geojson(ruby_geojson.to_str()).map_err(raise).unwrap();
+
+        wrap_road_class_mapping(mapping)
+    },
+    fn road_class_mapping_from_wkt(wkt: RString) -> AnyObject {
+        let ruby_wkt = wkt.map_err(|e| VM::raise_ex(e)).unwrap();
+        let mapping = RoadClassMapping::from_wkt(ruby_wkt.to_str()).map_err(raise).unwrap();
 
-        Module::from_existing("TracklibRwgps")
-            .get_nested_class("RoadClassMapping")
-            .wrap_data(inner, &*ROAD_CLASS_INNER_WRAPPER)
+        wrap_road_class_mapping(mapping)
     },
     fn road_class_mapping_add_road_class(road_class_id: Integer, surface_id: Integer) -> NilClass {
         let rc_id = road_class_id.map_err(|e| VM::raise_ex(e)).unwrap().to_u64();
@@ -58,6 +66,12 @@ methods!(
     }
 );
 
+fn wrap_road_class_mapping(mapping: RoadClassMapping) -> AnyObject {
+    Module::from_existing("TracklibRwgps")
+        .get_nested_class("RoadClassMapping")
+        .wrap_data(RoadClassInner { inner: mapping }, &*ROAD_CLASS_INNER_WRAPPER)
+}
+
 impl VerifiedObject for RubyRoadClassMapping {
     fn is_correct_type<T: Object>(object: &T) -> bool {
         object.class() == Module::from_existing("TracklibRwgps").get_nested_class("RoadClassMapping")
@@ -81,13 +95,14 @@ methods!(
     itself,
     fn surface_mapping_new(unknown_surface_id: Integer) -> AnyObject {
         let id = unknown_surface_id.map_err(|e| VM::raise_ex(e)).unwrap().to_u64();
-        let inner = SurfaceInner {
-            inner: crate::surface::rust::SurfaceMapping::new(id),
-        };
 
-        Module::from_existing("TracklibRwgps")
-            .get_nested_class("SurfaceMapping")
-            .wrap_data(inner, &*SURFACE_INNER_WRAPPER)
+        wrap_surface_mapping(SurfaceMapping::new(id))
+    },
+    fn surface_mapping_from_json(json: RString) -> AnyObject {
+        let ruby_json = json.map_err(|e| VM::raise_ex(e)).unwrap();
+        let mapping = SurfaceMapping::from_json(ruby_json.to_str()).map_err(raise).unwrap();
+
+        wrap_surface_mapping(mapping)
     },
     fn surface_mapping_add_surface(surface_id: Integer, group: RString) -> NilClass {
         let id = surface_id.map_err(|e| VM::raise_ex(e)).unwrap().to_u64();
@@ -105,6 +120,11 @@ methods!(
 
         NilClass::new()
     },
+    fn surface_mapping_to_json() -> RString {
+        let mapping = &itself.get_data(&*SURFACE_INNER_WRAPPER).inner;
+
+        RString::new_utf8(&mapping.to_json())
+    },
     fn surface_mapping_to_s() -> RString {
         let mapping = &itself.get_data(&*SURFACE_INNER_WRAPPER).inner;
 
@@ -112,6 +132,12 @@ methods!(
     }
 );
 
+fn wrap_surface_mapping(mapping: SurfaceMapping) -> AnyObject {
+    Module::from_existing("TracklibRwgps")
+        .get_nested_class("SurfaceMapping")
+        .wrap_data(SurfaceInner { inner: mapping }, &*SURFACE_INNER_WRAPPER)
+}
+
 impl RubySurfaceMapping {
     pub fn inner(&self) -> &SurfaceMapping {
         &self.get_data(&*SURFACE_INNER_WRAPPER).inner
@@ -1,5 +1,7 @@
+use crate::error::rust::TracklibRwgpsError;
 use crate::geometry::Point;
-use std::collections::HashMap;
+use crate::region::{Polygon, Region};
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 
 pub type SurfaceTypeId = u64;
@@ -7,24 +9,60 @@ pub type RoadClassId = u64;
 
 #[derive(Clone)]
 pub struct RoadClassMapping {
-    bbox: [f64; 4],
+    region: Region,
     map: HashMap<RoadClassId, SurfaceTypeId>,
 }
 
 impl RoadClassMapping {
     pub fn new(bbox: [f64; 4]) -> Self {
         Self {
-            bbox,
+            region: Region::Bbox(bbox),
             map: HashMap::new(),
         }
     }
 
+    // Like new, but rejects bboxes that aren't finite, since JSON can't hold them, or whose min latitude is above their
+    // max latitude. Longitudes may be inverted, that's how a bbox crossing the antimeridian is expressed.
+    pub fn try_new(bbox: [f64; 4]) -> Result<Self, TracklibRwgpsError> {
+        if bbox.iter().any(|v| !v.is_finite()) {
+            Err(TracklibRwgpsError::InvalidBbox("BBOX must be finite".to_string()))
+        } else if bbox[0] > bbox[2] {
+            Err(TracklibRwgpsError::InvalidBbox(
+                "BBOX min latitude must not be greater than max latitude".to_string(),
+            ))
+        } else {
+            Ok(Self::new(bbox))
+        }
+    }
+
+    pub fn with_polygons(polygons: Vec<Polygon>) -> Self {
+        Self {
+            region: Region::Polygons(polygons),
+            map: HashMap::new(),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn from_geojson(geojson: &str) -> Result<Self, TracklibRwgpsError> {
+        Ok(Self {
+            region: Region::from_geojson(geojson)?,
+            map: HashMap::new(),
+        })
+    }
+
+    pub fn from_wkt(wkt: &str) -> Result<Self, TracklibRwgpsError> {
+        Ok(Self {
+            region: Region::from_wkt(wkt)?,
+            map: HashMap::new(),
+        })
+    }
+
     pub fn add_road_class(&mut self, road_class_id: RoadClassId, surface_id: SurfaceTypeId) {
         self.map.insert(road_class_id, surface_id);
     }
 
     fn contains(&self, point: &Point) -> bool {
-        self.bbox[0] < point.y() && self.bbox[1] < point.x() && self.bbox[2] > point.y() && self.bbox[3] > point.x()
+        self.region.contains(point.x(), point.y())
     }
 
     fn lookup(&self, point: &Point) -> Option<&SurfaceTypeId> {
@@ -37,7 +75,100 @@ impl RoadClassMapping {
 
 impl fmt::Debug for RoadClassMapping {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "RoadClassMapping<bbox: {:?}, len: {}>", self.bbox, self.map.len())
+        match &self.region {
+            Region::Bbox(bbox) => write!(f, "RoadClassMapping<bbox: {:?}, len: {}>", bbox, self.map.len()),
+            Region::Polygons(polygons) => write!(
+                f,
+                "RoadClassMapping<polygons: {}, bbox: {:?}, len: {}>",
+                polygons.len(),
+                self.region.bbox(),
+                self.map.len()
+            ),
+        }
+    }
+}
+
+// Hierarchical grid over lon/lat. Each bbox is stored once, in the cell containing its min corner on the finest level
+// whose cells are at least as large as the bbox, so it can only reach into the neighbouring cells above and to the
+// right. A lookup visits the point's cell and the three cells below and to the left of it on every level in use.
+const ROAD_CLASS_INDEX_LEVELS: usize = 17;
+
+#[derive(Default)]
+struct RoadClassIndex {
+    levels: Vec<HashMap<(i64, i64), Vec<usize>>>,
+    // Bboxes that don't fit in the grid, checked on every lookup
+    unbounded: Vec<usize>,
+}
+
+impl RoadClassIndex {
+    fn cell_size(level: usize) -> f64 {
+        360.0 / (1 << level) as f64
+    }
+
+    fn cell(x: f64, y: f64, size: f64) -> (i64, i64) {
+        ((x / size).floor() as i64, (y / size).floor() as i64)
+    }
+
+    fn insert(&mut self, bbox: &[f64; 4], index: usize) {
+        if bbox[1] > bbox[3] {
+            // Crosses the antimeridian, index the parts on either side of it
+            self.insert(&[bbox[0], bbox[1], bbox[2], bbox[1].max(180.0)], index);
+            self.insert(&[bbox[0], bbox[3].min(-180.0), bbox[2], bbox[3]], index);
+            return;
+        }
+
+        let extent = (bbox[2] - bbox[0]).max(bbox[3] - bbox[1]);
+
+        if !bbox.iter().all(|v| v.is_finite()) || extent > Self::cell_size(0) {
+            self.unbounded.push(index);
+            return;
+        }
+
+        let level = (0..ROAD_CLASS_INDEX_LEVELS)
+            .take_while(|level| Self::cell_size(*level) >= extent)
+            .last()
+            .unwrap_or(0);
+
+        if self.levels.len() <= level {
+            self.levels.resize_with(level + 1, HashMap::new);
+        }
+        self.levels[level]
+            .entry(Self::cell(bbox[1], bbox[0], Self::cell_size(level)))
+            .or_default()
+            .push(index);
+    }
+
+    // Runs `lookup` on the bboxes that might contain the point and returns the match with the lowest index. Each cell
+    // lists its indexes in insertion order, so a cell is only scanned up to its first match or the best one so far.
+    fn find_first<T>(&self, point: &Point, lookup: impl Fn(usize) -> Option<T>) -> Option<T> {
+        let mut first: Option<(usize, T)> = None;
+        let mut scan = |indexes: &[usize]| {
+            for &index in indexes {
+                if first.as_ref().is_some_and(|(first_index, _)| *first_index <= index) {
+                    break;
+                }
+                if let Some(found) = lookup(index) {
+                    first = Some((index, found));
+                    break;
+                }
+            }
+        };
+
+        scan(&self.unbounded);
+        for (level, cells) in self.levels.iter().enumerate() {
+            if cells.is_empty() {
+                continue;
+            }
+
+            let (x, y) = Self::cell(point.x(), point.y(), Self::cell_size(level));
+            for cell in [(x, y), (x - 1, y), (x, y - 1), (x - 1, y - 1)] {
+                if let Some(indexes) = cells.get(&cell) {
+                    scan(indexes);
+                }
+            }
+        }
+
+        first.map(|(_, found)| found)
     }
 }
 
@@ -45,6 +176,7 @@ pub struct SurfaceMapping {
     unknown_surface_id: SurfaceTypeId,
     groups: HashMap<SurfaceTypeId, String>,
     road_class_mappings: Vec<RoadClassMapping>,
+    road_class_index: RoadClassIndex,
 }
 
 impl SurfaceMapping {
@@ -53,6 +185,7 @@ impl SurfaceMapping {
             unknown_surface_id,
             groups: HashMap::new(),
             road_class_mappings: Vec::new(),
+            road_class_index: RoadClassIndex::default(),
         }
     }
 
@@ -61,15 +194,17 @@ impl SurfaceMapping {
     }
 
     pub fn add_road_class_mapping(&mut self, road_class_mapping: RoadClassMapping) {
+        self.road_class_index
+            .insert(&road_class_mapping.region.bbox(), self.road_class_mappings.len());
         self.road_class_mappings.push(road_class_mapping);
     }
 
-    pub(crate) fn get_surface_group(&self, point: &Point) -> Option<&String> {
+    pub fn get_surface_group(&self, point: &Point) -> Option<&String> {
         if let Some(point_surface) = point.s() {
             if point_surface == self.unknown_surface_id {
-                self.road_class_mappings
-                    .iter()
-                    .find_map(|road_class_mapping| road_class_mapping.lookup(&point))
+                // The first mapping added wins when several of them contain the point
+                self.road_class_index
+                    .find_first(point, |index| self.road_class_mappings[index].lookup(point))
                     .and_then(|surface_id| self.groups.get(surface_id))
             } else {
                 self.groups.get(&point_surface)
@@ -80,6 +215,118 @@ impl SurfaceMapping {
     }
 }
 
+// Plain data form of a SurfaceMapping, read and written by from_json and to_json and the serde impls. Maps are ordered
+// so the output is stable enough to keep under version control.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct SurfaceMappingConfig {
+    unknown_surface_id: SurfaceTypeId,
+    #[cfg_attr(feature = "serde", serde(default))]
+    groups: BTreeMap<SurfaceTypeId, String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    road_class_mappings: Vec<RoadClassMappingConfig>,
+}
+
+// Exactly one of bbox and polygons is set, polygons being the coordinates of a GeoJSON MultiPolygon
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct RoadClassMappingConfig {
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    bbox: Option<[f64; 4]>,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    polygons: Option<Vec<Vec<Vec<[f64; 2]>>>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    road_classes: BTreeMap<RoadClassId, SurfaceTypeId>,
+}
+
+impl From<&SurfaceMapping> for SurfaceMappingConfig {
+    fn from(mapping: &SurfaceMapping) -> Self {
+        Self {
+            unknown_surface_id: mapping.unknown_surface_id,
+            groups: mapping.groups.iter().map(|(id, group)| (*id, group.clone())).collect(),
+            road_class_mappings: mapping
+                .road_class_mappings
+                .iter()
+                .map(|road_class_mapping| {
+                    let (bbox, polygons) = match &road_class_mapping.region {
+                        Region::Bbox(bbox) => (Some(*bbox), None),
+                        Region::Polygons(polygons) => (
+                            None,
+                            Some(polygons.iter().map(|polygon| polygon.rings().to_vec()).collect()),
+                        ),
+                    };
+                    RoadClassMappingConfig {
+                        bbox,
+                        polygons,
+                        road_classes: road_class_mapping.map.iter().map(|(r, s)| (*r, *s)).collect(),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<SurfaceMappingConfig> for SurfaceMapping {
+    type Error = TracklibRwgpsError;
+
+    fn try_from(config: SurfaceMappingConfig) -> Result<Self, Self::Error> {
+        let mut mapping = SurfaceMapping::new(config.unknown_surface_id);
+        for (surface_id, group) in config.groups {
+            mapping.add_surface(surface_id, group);
+        }
+
+        for road_class_mapping_config in config.road_class_mappings {
+            let mut road_class_mapping = match (road_class_mapping_config.bbox, road_class_mapping_config.polygons) {
+                (Some(bbox), None) => RoadClassMapping::try_new(bbox)?,
+                (None, Some(polygons)) => {
+                    RoadClassMapping::with_polygons(polygons.into_iter().map(Polygon::new).collect::<Result<_, _>>()?)
+                }
+                _ => {
+                    return Err(TracklibRwgpsError::InvalidSurfaceMapping(
+                        "Road class mappings need either a bbox or polygons".to_string(),
+                    ))
+                }
+            };
+            for (road_class_id, surface_id) in road_class_mapping_config.road_classes {
+                road_class_mapping.add_road_class(road_class_id, surface_id);
+            }
+            mapping.add_road_class_mapping(road_class_mapping);
+        }
+
+        Ok(mapping)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl SurfaceMapping {
+    pub fn from_json(json: &str) -> Result<Self, TracklibRwgpsError> {
+        serde_json::from_str::<SurfaceMappingConfig>(json)
+            .map_err(|e| TracklibRwgpsError::InvalidSurfaceMapping(format!("Invalid JSON: {e}")))?
+            .try_into()
+    }
+
+    pub fn to_json(&self) -> String {
+        // Every key is an integer id and try_new keeps bboxes finite, so there's nothing serde_json can't write
+        serde_json::to_string(&SurfaceMappingConfig::from(self)).unwrap()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for SurfaceMapping {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SurfaceMappingConfig::from(self).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SurfaceMapping {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        SurfaceMappingConfig::deserialize(deserializer)?
+            .try_into()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 impl fmt::Debug for SurfaceMapping {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -95,6 +342,7 @@ impl fmt::Debug for SurfaceMapping {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::geometry::tests::lcg;
 
     #[test]
     fn test_simple_surface_groups() {
@@ -180,4 +428,189 @@ mod tests {
             Some(&"23".to_string())
         );
     }
+
+    #[test]
+    fn test_road_class_polygon_region() {
+        let mut mapping = SurfaceMapping::new(95);
+        mapping.add_surface(0, "0".to_string());
+        mapping.add_surface(1, "1".to_string());
+
+        // An L shaped region, its bbox covers the empty top right quarter
+        mapping.add_road_class_mapping({
+            let mut rc_mapping =
+                RoadClassMapping::from_wkt("POLYGON ((0 0, 10 0, 10 5, 5 5, 5 10, 0 10, 0 0))").unwrap();
+            rc_mapping.add_road_class(10, 0);
+            rc_mapping
+        });
+
+        mapping.add_road_class_mapping({
+            let mut rc_mapping = RoadClassMapping::new([-90.0, -180.0, 90.0, 180.0]);
+            rc_mapping.add_road_class(10, 1);
+            rc_mapping
+        });
+
+        assert_eq!(
+            mapping.get_surface_group(&Point::new(0, 2.0, 8.0, 0.0, 0.0, Some(95), Some(10))),
+            Some(&"0".to_string())
+        );
+        assert_eq!(
+            mapping.get_surface_group(&Point::new(0, 8.0, 8.0, 0.0, 0.0, Some(95), Some(10))),
+            Some(&"1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_road_class_antimeridian() {
+        let mut mapping = SurfaceMapping::new(95);
+        mapping.add_surface(0, "0".to_string());
+
+        mapping.add_road_class_mapping({
+            let mut rc_mapping = RoadClassMapping::try_new([-20.0, 175.0, -10.0, -178.0]).unwrap();
+            rc_mapping.add_road_class(10, 0);
+            rc_mapping
+        });
+
+        for (x, expected) in [
+            (178.0, Some(&"0".to_string())),
+            (-179.0, Some(&"0".to_string())),
+            (0.0, None),
+        ] {
+            assert_eq!(
+                mapping.get_surface_group(&Point::new(0, x, -15.0, 0.0, 0.0, Some(95), Some(10))),
+                expected
+            );
+        }
+
+        assert!(RoadClassMapping::try_new([10.0, -20.0, -10.0, 20.0]).is_err());
+        assert!(RoadClassMapping::try_new([-10.0, f64::NAN, 10.0, 20.0]).is_err());
+        assert!(RoadClassMapping::try_new([f64::NEG_INFINITY, -180.0, f64::INFINITY, 180.0]).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_json_round_trip() {
+        let mut mapping = SurfaceMapping::new(99);
+        mapping.add_surface(20, "Gravel".to_string());
+        mapping.add_surface(1, "Paved \"smooth\"".to_string());
+
+        mapping.add_road_class_mapping({
+            let mut rc_mapping = RoadClassMapping::new([-20.0, 175.5, -10.0, -178.0]);
+            rc_mapping.add_road_class(11, 20);
+            rc_mapping.add_road_class(10, 1);
+            rc_mapping
+        });
+        mapping.add_road_class_mapping({
+            let mut rc_mapping = RoadClassMapping::from_wkt("POLYGON ((0 0, 10 0, 10 5, 0 5))").unwrap();
+            rc_mapping.add_road_class(10, 20);
+            rc_mapping
+        });
+
+        let json = mapping.to_json();
+        assert_eq!(
+            json,
+            concat!(
+                r#"{"unknown_surface_id":99,"groups":{"1":"Paved \"smooth\"","20":"Gravel"},"road_class_mappings":["#,
+                r#"{"bbox":[-20.0,175.5,-10.0,-178.0],"road_classes":{"10":1,"11":20}},"#,
+                r#"{"polygons":[[[[0.0,0.0],[10.0,0.0],[10.0,5.0],[0.0,5.0]]]],"road_classes":{"10":20}}]}"#
+            )
+        );
+
+        let read = SurfaceMapping::from_json(&json).unwrap();
+        assert_eq!(read.to_json(), json);
+        assert_eq!(
+            read.get_surface_group(&Point::new(0, 179.0, -15.0, 0.0, 0.0, Some(99), Some(11))),
+            Some(&"Gravel".to_string())
+        );
+        assert_eq!(
+            read.get_surface_group(&Point::new(0, 5.0, 2.0, 0.0, 0.0, Some(99), Some(10))),
+            Some(&"Gravel".to_string())
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_matches_json() {
+        // Integer coordinates are read as floats and written back with a fraction
+        let json = concat!(
+            r#"{"unknown_surface_id":99,"groups":{"1":"Paved","20":"Gravel"},"road_class_mappings":["#,
+            r#"{"bbox":[-20,175.5,-10,-178],"road_classes":{"10":1,"11":20}},"#,
+            r#"{"polygons":[[[[0,0],[10,0],[10,5],[0,5]]]],"road_classes":{"10":20}}]}"#
+        );
+
+        let mapping: SurfaceMapping = serde_json::from_str(json).unwrap();
+        assert_eq!(serde_json::to_string(&mapping).unwrap(), mapping.to_json());
+        assert_eq!(SurfaceMapping::from_json(json).unwrap().to_json(), mapping.to_json());
+
+        assert!(
+            serde_json::from_str::<SurfaceMapping>(r#"{"unknown_surface_id":99,"road_class_mappings":[{}]}"#).is_err()
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_invalid_json() {
+        assert!(SurfaceMapping::from_json(r#"{"unknown_surface_id":99}"#).is_ok());
+
+        for json in [
+            "",
+            r#"{"groups":{}}"#,
+            r#"{"unknown_surface_id":-1}"#,
+            r#"{"unknown_surface_id":99,"groups":{"a":"Paved"}}"#,
+            r#"{"unknown_surface_id":99,"groups":{"1":2}}"#,
+            r#"{"unknown_surface_id":99,"road_class_mappings":[{"road_classes":{}}]}"#,
+            r#"{"unknown_surface_id":99,"road_class_mappings":[{"bbox":[1,2,3]}]}"#,
+            r#"{"unknown_surface_id":99,"road_class_mappings":[{"bbox":[10,0,-10,1]}]}"#,
+            r#"{"unknown_surface_id":99,"road_class_mappings":[{"bbox":[null,-180,90,180]}]}"#,
+            r#"{"unknown_surface_id":99,"road_class_mappings":[{"polygons":[[[[0,0],[1,1]]]]}]}"#,
+            r#"{"unknown_surface_id":99,"road_class_mappings":[{"bbox":[0,0,1,1],"road_classes":{"1":"a"}}]}"#,
+        ] {
+            assert!(SurfaceMapping::from_json(json).is_err(), "{json}");
+        }
+    }
+
+    #[test]
+    fn test_road_class_index_matches_linear_scan() {
+        let mut lcg = lcg(42);
+        let mut next = move |range: f64| lcg() * range;
+
+        let mut mapping = SurfaceMapping::new(95);
+        mapping.add_surface(0, "0".to_string());
+        mapping.add_surface(1, "1".to_string());
+
+        let mut rc_mappings = Vec::new();
+        for i in 0..2000 {
+            let size = [0.001, 0.1, 5.0, 60.0][i % 4] * next(1.0);
+            let min_lat = next(20.0) - 10.0;
+            let min_lon = next(20.0) - 10.0;
+            let mut rc_mapping = RoadClassMapping::new([min_lat, min_lon, min_lat + size, min_lon + size]);
+            rc_mapping.add_road_class(i as u64 % 3, i as u64 % 2);
+            rc_mappings.push(rc_mapping.clone());
+            mapping.add_road_class_mapping(rc_mapping);
+        }
+
+        // A bbox covering the whole world, checked without the grid
+        let mut rc_mapping =
+            RoadClassMapping::new([f64::NEG_INFINITY, f64::NEG_INFINITY, f64::INFINITY, f64::INFINITY]);
+        rc_mapping.add_road_class(3, 0);
+        rc_mappings.push(rc_mapping.clone());
+        mapping.add_road_class_mapping(rc_mapping);
+
+        for _ in 0..2000 {
+            let point = Point::new(
+                0,
+                next(24.0) - 12.0,
+                next(24.0) - 12.0,
+                0.0,
+                0.0,
+                Some(95),
+                Some(next(4.0) as u64),
+            );
+            let expected = rc_mappings
+                .iter()
+                .find_map(|rc_mapping| rc_mapping.lookup(&point))
+                .and_then(|surface_id| mapping.groups.get(surface_id));
+
+            assert_eq!(mapping.get_surface_group(&point), expected);
+        }
+    }
 }
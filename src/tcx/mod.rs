@@ -0,0 +1,3 @@
+#[cfg(feature = "ruby")]
+pub(crate) mod ruby;
+pub(crate) mod rust;
@@ -0,0 +1,84 @@
+use super::rust::tcx_encode;
+use crate::error::ruby::raise;
+use crate::error::rust::TracklibRwgpsError;
+use crate::geometry::{section_to_points, IrrelevantPointsBehavior};
+use crate::ruby::key_material_from_ruby;
+use crate::schema::ruby::RubyPointSchema;
+use crate::schema::rust::PointSchema;
+use crate::simplify::ruby::optional_simplify_options_from_ruby;
+use crate::simplify::rust::simplify_points;
+use crate::surface::ruby::RubySurfaceMapping;
+use itertools::Itertools;
+use rutie::{methods, module, AnyObject, Integer, Object, RString, VM};
+
+module!(TracklibRwgps);
+
+methods!(
+    TracklibRwgps,
+    _rtself,
+    fn tcx_section_data_tcx(
+        track_reader: ruby_tracklib::read::TrackReader,
+        index: Integer,
+        name: RString,
+        mapping: RubySurfaceMapping,
+        tolerance: AnyObject,
+        key_material: AnyObject,
+        point_schema: RubyPointSchema) -> RString {
+        let ruby_track_reader = track_reader.map_err(VM::raise_ex).unwrap();
+
+        let ruby_index = index.map_err(VM::raise_ex).unwrap();
+        let rust_index = usize::try_from(ruby_index.to_u64())
+            .map_err(|_| raise(TracklibRwgpsError::SectionMissing))
+            .unwrap();
+
+        let ruby_name = name.ok();
+        let rust_name = ruby_name.as_ref().map(|name| name.to_str());
+
+        // Points are only simplified when given both a SurfaceMapping and a tolerance
+        let ruby_mapping = mapping.ok();
+        let rust_simplify_opts = optional_simplify_options_from_ruby(tolerance.ok());
+        if ruby_mapping.is_some() != rust_simplify_opts.is_some() {
+            raise(TracklibRwgpsError::InvalidSimplifyOption(
+                "Simplifying a TCX needs both a SurfaceMapping and a tolerance".to_string(),
+            ));
+        }
+
+        let ruby_key_material = key_material.ok().and_then(key_material_from_ruby);
+        let rust_key_material = ruby_key_material.as_ref().map(|key| key.to_bytes_unchecked());
+
+        let rust_point_schema = point_schema
+            .ok()
+            .map_or_else(PointSchema::new, |ruby_point_schema| ruby_point_schema.inner().clone());
+
+        ruby_track_reader.with_track_reader(|track_reader| {
+            let mut section = track_reader
+                .section(rust_index)
+                .ok_or(TracklibRwgpsError::SectionMissing)
+                .map_err(raise)
+                .unwrap();
+            let points = section_to_points(
+                &mut section,
+                rust_key_material,
+                &rust_point_schema,
+                None,
+                IrrelevantPointsBehavior::Ignore,
+            )
+            .map_err(raise)
+            .unwrap();
+
+            let tcx = match (&ruby_mapping, &rust_simplify_opts) {
+                (Some(ruby_mapping), Some(rust_simplify_opts)) => {
+                    let simplified_points = simplify_points(&points, ruby_mapping.inner(), rust_simplify_opts)
+                        .into_iter()
+                        .sorted()
+                        .map(|index| points[index].clone())
+                        .collect::<Vec<_>>();
+                    tcx_encode(&simplified_points, rust_name)
+                }
+                _ => tcx_encode(&points, rust_name),
+            };
+
+            RString::from(tcx)
+        })
+    }
+);
@@ -0,0 +1,200 @@
+use crate::geometry::Point;
+use crate::xml;
+use itertools::Itertools;
+use std::fmt::Write;
+
+// The TCX schema requires a course name of 1 to 15 characters, after collapsing whitespace
+const DEFAULT_NAME: &str = "Course";
+const MAX_NAME_LENGTH: usize = 15;
+
+// Speed in meters per second used to give untimed points a time, as the schema requires one on every trackpoint
+const SYNTHESIZED_SPEED: f64 = 5.0;
+
+fn write_position(output: &mut String, tag: &str, point: &Point) {
+    write!(
+        output,
+        "<{tag}><LatitudeDegrees>{}</LatitudeDegrees><LongitudeDegrees>{}</LongitudeDegrees></{tag}>",
+        point.y(),
+        point.x()
+    )
+    .unwrap();
+}
+
+fn write_lap(output: &mut String, (first, first_t): (&Point, i64), (last, last_t): (&Point, i64)) {
+    write!(
+        output,
+        "<Lap><TotalTimeSeconds>{}</TotalTimeSeconds><DistanceMeters>{}</DistanceMeters>",
+        last_t - first_t,
+        last.d() - first.d()
+    )
+    .unwrap();
+    write_position(output, "BeginPosition", first);
+    write_position(output, "EndPosition", last);
+    output.push_str("<Intensity>Active</Intensity></Lap>");
+}
+
+fn write_trackpoint(output: &mut String, point: &Point, t: i64) {
+    output.push_str("<Trackpoint><Time>");
+    xml::write_timestamp(output, t);
+    output.push_str("</Time>");
+    write_position(output, "Position", point);
+    write!(
+        output,
+        "<AltitudeMeters>{}</AltitudeMeters><DistanceMeters>{}</DistanceMeters>",
+        point.e(),
+        point.d()
+    )
+    .unwrap();
+    output.push_str("</Trackpoint>");
+}
+
+fn course_name(name: Option<&str>) -> String {
+    let name = name.unwrap_or_default().split_whitespace().join(" ");
+    if name.is_empty() {
+        DEFAULT_NAME.to_string()
+    } else {
+        name.chars()
+            .take(MAX_NAME_LENGTH)
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+}
+
+// Points keep their own time. One without a time is timed from the last timed point before it, or the first one after
+// it at the start of the course, as if the distance between them were covered at SYNTHESIZED_SPEED. Without any timed
+// points the course starts at the unix epoch.
+fn course_times(points: &[Point]) -> Vec<i64> {
+    let mut anchor = points
+        .iter()
+        .find_map(|point| point.t().map(|t| (t, point.d())))
+        .unwrap_or((0, points.first().map_or(0.0, Point::d)));
+    points
+        .iter()
+        .map(|point| match point.t() {
+            Some(t) => {
+                anchor = (t, point.d());
+                t
+            }
+            None => anchor.0 + ((point.d() - anchor.1) / SYNTHESIZED_SPEED).round() as i64,
+        })
+        .collect()
+}
+
+// Writes the points as a TCX course. DistanceMeters is the cumulative distance from `Point::d`, so it stays the true
+// distance along the track even when the points have been simplified.
+pub fn tcx_encode(points: &[Point], name: Option<&str>) -> String {
+    let times = course_times(points);
+
+    let mut output = String::new();
+    output.push_str(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    output.push_str(r#"<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">"#);
+    output.push_str("<Courses><Course>");
+
+    output.push_str("<Name>");
+    xml::escape(&mut output, &course_name(name));
+    output.push_str("</Name>");
+    if let (Some(first), Some(last)) = (points.first(), points.last()) {
+        write_lap(&mut output, (first, times[0]), (last, times[times.len() - 1]));
+    }
+    output.push_str("<Track>");
+    for (point, t) in points.iter().zip(times) {
+        write_trackpoint(&mut output, point, t);
+    }
+    output.push_str("</Track>");
+
+    output.push_str("</Course></Courses></TrainingCenterDatabase>");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tcx_course() {
+        let points = vec![
+            Point::new(0, -122.5, 45.25, 0.0, 10.0, None, None).with_time(Some(1_600_000_000), None),
+            Point::new(1, -122.0, 45.5, 1500.5, 12.5, None, None).with_time(Some(1_600_000_300), None),
+        ];
+
+        assert_eq!(
+            tcx_encode(&points, Some("Loop")),
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                r#"<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">"#,
+                "<Courses><Course><Name>Loop</Name>",
+                "<Lap><TotalTimeSeconds>300</TotalTimeSeconds><DistanceMeters>1500.5</DistanceMeters>",
+                "<BeginPosition><LatitudeDegrees>45.25</LatitudeDegrees><LongitudeDegrees>-122.5</LongitudeDegrees></BeginPosition>",
+                "<EndPosition><LatitudeDegrees>45.5</LatitudeDegrees><LongitudeDegrees>-122</LongitudeDegrees></EndPosition>",
+                "<Intensity>Active</Intensity></Lap>",
+                "<Track>",
+                "<Trackpoint><Time>2020-09-13T12:26:40Z</Time>",
+                "<Position><LatitudeDegrees>45.25</LatitudeDegrees><LongitudeDegrees>-122.5</LongitudeDegrees></Position>",
+                "<AltitudeMeters>10</AltitudeMeters><DistanceMeters>0</DistanceMeters></Trackpoint>",
+                "<Trackpoint><Time>2020-09-13T12:31:40Z</Time>",
+                "<Position><LatitudeDegrees>45.5</LatitudeDegrees><LongitudeDegrees>-122</LongitudeDegrees></Position>",
+                "<AltitudeMeters>12.5</AltitudeMeters><DistanceMeters>1500.5</DistanceMeters></Trackpoint>",
+                "</Track></Course></Courses></TrainingCenterDatabase>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_untimed_unnamed_course() {
+        let points = vec![
+            Point::new(0, -122.5, 45.25, 100.0, 10.0, None, None),
+            Point::new(1, -122.0, 45.5, 1600.5, 12.5, None, None),
+        ];
+
+        // Course_t requires a Name, and Trackpoint_t a Time
+        assert_eq!(
+            tcx_encode(&points, None),
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                r#"<TrainingCenterDatabase xmlns="http://www.garmin.com/xmlschemas/TrainingCenterDatabase/v2">"#,
+                "<Courses><Course><Name>Course</Name>",
+                "<Lap><TotalTimeSeconds>300</TotalTimeSeconds><DistanceMeters>1500.5</DistanceMeters>",
+                "<BeginPosition><LatitudeDegrees>45.25</LatitudeDegrees><LongitudeDegrees>-122.5</LongitudeDegrees></BeginPosition>",
+                "<EndPosition><LatitudeDegrees>45.5</LatitudeDegrees><LongitudeDegrees>-122</LongitudeDegrees></EndPosition>",
+                "<Intensity>Active</Intensity></Lap>",
+                "<Track>",
+                "<Trackpoint><Time>1970-01-01T00:00:00Z</Time>",
+                "<Position><LatitudeDegrees>45.25</LatitudeDegrees><LongitudeDegrees>-122.5</LongitudeDegrees></Position>",
+                "<AltitudeMeters>10</AltitudeMeters><DistanceMeters>100</DistanceMeters></Trackpoint>",
+                "<Trackpoint><Time>1970-01-01T00:05:00Z</Time>",
+                "<Position><LatitudeDegrees>45.5</LatitudeDegrees><LongitudeDegrees>-122</LongitudeDegrees></Position>",
+                "<AltitudeMeters>12.5</AltitudeMeters><DistanceMeters>1600.5</DistanceMeters></Trackpoint>",
+                "</Track></Course></Courses></TrainingCenterDatabase>"
+            )
+        );
+        assert!(tcx_encode(&[], None).contains("<Name>Course</Name><Track></Track>"));
+    }
+
+    #[test]
+    fn test_course_name() {
+        assert_eq!(course_name(Some("Loop")), "Loop");
+        assert_eq!(course_name(Some("  Morning \n Ride ")), "Morning Ride");
+        assert_eq!(course_name(Some("Around the Lake & Back")), "Around the Lake");
+        assert_eq!(course_name(Some("Ride to the summit")), "Ride to the sum");
+        assert_eq!(course_name(Some("Ride to the  top of it")), "Ride to the top");
+        assert_eq!(course_name(Some("Ride to the 12 miles")), "Ride to the 12");
+        assert_eq!(course_name(Some(" ")), "Course");
+        assert_eq!(course_name(None), "Course");
+    }
+
+    #[test]
+    fn test_course_times() {
+        let points = vec![
+            Point::new(0, 0.0, 0.0, 0.0, 0.0, None, None),
+            Point::new(1, 0.0, 0.0, 50.0, 0.0, None, None).with_time(Some(1_600_000_000), None),
+            Point::new(2, 0.0, 0.0, 100.0, 0.0, None, None),
+            Point::new(3, 0.0, 0.0, 152.0, 0.0, None, None).with_time(Some(1_600_000_020), None),
+        ];
+
+        assert_eq!(
+            course_times(&points),
+            [1_599_999_990, 1_600_000_000, 1_600_000_010, 1_600_000_020]
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use super::rust::track_to_points;
+use crate::error::ruby::raise;
+use crate::error::rust::TracklibRwgpsError;
+use crate::geometry::{with_section_reader, IrrelevantPointsBehavior};
+use crate::polyline::ruby::PolylineOptions;
+use crate::polyline::rust::polyline_encode;
+use crate::ruby::key_material_from_ruby;
+use crate::schema::ruby::RubyPointSchema;
+use crate::schema::rust::PointSchema;
+use crate::simplify::ruby::{reader_with_indexes_to_array_of_hashes, simplify_options_from_ruby};
+use crate::simplify::rust::simplify_points;
+use crate::surface::ruby::RubySurfaceMapping;
+use rutie::{methods, module, AnyObject, Array, Float, Hash, Object, RString, VM};
+
+// Track functions accept either one key for every section or an Array with a key (or nil) per section
+fn keys_from_ruby(key_material: Option<AnyObject>, section_count: usize) -> Vec<Option<RString>> {
+    match key_material {
+        Some(key_material) => match key_material.try_convert_to::<Array>() {
+            Ok(keys) => (0..section_count)
+                .map(|index| key_material_from_ruby(keys.at(index as i64)))
+                .collect(),
+            Err(_) => vec![key_material_from_ruby(key_material); section_count],
+        },
+        None => vec![None; section_count],
+    }
+}
+
+module!(TracklibRwgps);
+
+methods!(
+    TracklibRwgps,
+    _rtself,
+    fn track_track_data_polyline(
+        track_reader: ruby_tracklib::read::TrackReader,
+        polyline_opts: PolylineOptions,
+        key_material: AnyObject,
+        point_schema: RubyPointSchema) -> RString {
+        let ruby_track_reader = track_reader.map_err(VM::raise_ex).unwrap();
+
+        let ruby_polyline_opts = polyline_opts.map_err(VM::raise_ex).unwrap();
+        let rust_polyline_opts = ruby_polyline_opts.inner();
+
+        let ruby_key_material = key_material.ok();
+
+        let rust_point_schema = point_schema
+            .ok()
+            .map_or_else(PointSchema::new, |ruby_point_schema| ruby_point_schema.inner().clone());
+
+        ruby_track_reader.with_track_reader(|track_reader| {
+            let mut sections = (0..).map_while(|index| track_reader.section(index)).collect::<Vec<_>>();
+
+            let ruby_keys = keys_from_ruby(ruby_key_material, sections.len());
+            let rust_keys = ruby_keys
+                .iter()
+                .map(|key| key.as_ref().map(|key| key.to_bytes_unchecked()))
+                .collect::<Vec<_>>();
+
+            let points = track_to_points(
+                &mut sections,
+                &rust_keys,
+                &rust_point_schema,
+                IrrelevantPointsBehavior::Ignore,
+            )
+            .map_err(raise)
+            .unwrap()
+            .concat();
+
+            RString::from(polyline_encode(&points, rust_polyline_opts))
+        })
+    },
+    fn track_track_data_simplified(
+        track_reader: ruby_tracklib::read::TrackReader,
+        mapping: RubySurfaceMapping,
+        tolerance: AnyObject,
+        key_material: AnyObject,
+        point_schema: RubyPointSchema) -> Array {
+        let ruby_track_reader = track_reader.map_err(VM::raise_ex).unwrap();
+
+        let rust_simplify_opts = simplify_options_from_ruby(tolerance.map_err(VM::raise_ex).unwrap());
+
+        let ruby_mapping = mapping.map_err(VM::raise_ex).unwrap();
+        let rust_mapping = ruby_mapping.inner();
+
+        let ruby_key_material = key_material.ok();
+
+        let rust_point_schema = point_schema
+            .ok()
+            .map_or_else(PointSchema::new, |ruby_point_schema| ruby_point_schema.inner().clone());
+
+        ruby_track_reader.with_track_reader(|track_reader| {
+            let mut sections = (0..).map_while(|index| track_reader.section(index)).collect::<Vec<_>>();
+
+            let ruby_keys = keys_from_ruby(ruby_key_material, sections.len());
+            let rust_keys = ruby_keys
+                .iter()
+                .map(|key| key.as_ref().map(|key| key.to_bytes_unchecked()))
+                .collect::<Vec<_>>();
+
+            let track_points = track_to_points(
+                &mut sections,
+                &rust_keys,
+                &rust_point_schema,
+                IrrelevantPointsBehavior::Count,
+            )
+            .map_err(raise)
+            .unwrap();
+
+            let mut data_array = Array::new();
+            for ((section, key), points) in sections.iter_mut().zip(rust_keys).zip(track_points) {
+                // Each section is simplified on its own, so section boundaries are always kept
+                let simplified_indexes = simplify_points(&points, &rust_mapping, &rust_simplify_opts);
+
+                let section_array = with_section_reader(section, None, key, |section_reader_for_serialization| {
+                    reader_with_indexes_to_array_of_hashes(
+                        section_reader_for_serialization,
+                        &points,
+                        &simplified_indexes,
+                    )
+                })
+                .map_err(raise)
+                .unwrap();
+
+                // Rows come back in index order, alongside the points they were read from. "d" is reserved, the
+                // distance along the whole track replaces any "d" column the section has.
+                let simplified_points = points
+                    .iter()
+                    .filter(|point| simplified_indexes.contains(&point.index()))
+                    .collect::<Vec<_>>();
+                if section_array.length() != simplified_points.len() {
+                    raise(TracklibRwgpsError::RowCountMismatch(
+                        section_array.length(),
+                        simplified_points.len(),
+                    ));
+                }
+                for (row_hash, point) in section_array.into_iter().zip(simplified_points) {
+                    let mut row_hash = row_hash.try_convert_to::<Hash>().map_err(VM::raise_ex).unwrap();
+                    row_hash.store(RString::from("d"), Float::new(point.d()));
+                    data_array.push(row_hash);
+                }
+            }
+
+            data_array
+        })
+    }
+);
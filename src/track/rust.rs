@@ -0,0 +1,29 @@
+use crate::error::rust::TracklibRwgpsError;
+use crate::geometry::{section_to_points, IrrelevantPointsBehavior, Point};
+use crate::schema::rust::PointSchema;
+use tracklib::read::section::Section;
+
+// Reads the points of every section in order. Each section's first point continues on from the last point of the one
+// before it, so `d` is the distance along the whole track. `key_material` is per section, missing entries are None.
+pub fn track_to_points(
+    sections: &mut [Section],
+    key_material: &[Option<&[u8]>],
+    point_schema: &PointSchema,
+    irrelevant_points_behavior: IrrelevantPointsBehavior,
+) -> Result<Vec<Vec<Point>>, TracklibRwgpsError> {
+    let mut track_points: Vec<Vec<Point>> = Vec::with_capacity(sections.len());
+
+    for (index, section) in sections.iter_mut().enumerate() {
+        let previous_point = track_points.iter().rev().find_map(|points| points.last());
+        let points = section_to_points(
+            section,
+            key_material.get(index).copied().flatten(),
+            point_schema,
+            previous_point,
+            irrelevant_points_behavior,
+        )?;
+        track_points.push(points);
+    }
+
+    Ok(track_points)
+}
@@ -0,0 +1,66 @@
+use std::fmt::Write;
+
+pub(crate) fn escape(output: &mut String, s: &str) {
+    for c in s.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&apos;"),
+            c => output.push(c),
+        }
+    }
+}
+
+// Writes seconds since the unix epoch as an xsd:dateTime in UTC, e.g. 2020-09-13T12:26:40Z
+pub(crate) fn write_timestamp(output: &mut String, t: i64) {
+    let days = t.div_euclid(86_400);
+    let seconds = t.rem_euclid(86_400);
+
+    // Howard Hinnant's civil_from_days, see https://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    write!(
+        output,
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+    .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        let mut output = String::new();
+        escape(&mut output, r#"Tom & Jerry's <"ride">"#);
+        assert_eq!(output, "Tom &amp; Jerry&apos;s &lt;&quot;ride&quot;&gt;");
+    }
+
+    #[test]
+    fn test_write_timestamp() {
+        for (t, expected) in [
+            (0, "1970-01-01T00:00:00Z"),
+            (1_600_000_000, "2020-09-13T12:26:40Z"),
+            (951_825_600, "2000-02-29T12:00:00Z"),
+            (-1, "1969-12-31T23:59:59Z"),
+        ] {
+            let mut output = String::new();
+            write_timestamp(&mut output, t);
+            assert_eq!(output, expected);
+        }
+    }
+}